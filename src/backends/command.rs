@@ -0,0 +1,111 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// src/backends/command.rs
+
+use super::{file_uri, run, WallpaperBackend};
+use crate::core_models::{DwBackendConfig, DwDisplayConfig, DwFillMode};
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+/// Runs the argv template from the `backend` section of the config.
+///
+/// Placeholders are substituted inside each argument and the result is executed
+/// directly, never through a shell, so a path can't change the command line.
+pub struct CommandBackend {
+    command: Vec<String>,
+    mode: DwFillMode,
+    output: Option<String>,
+}
+
+impl CommandBackend {
+    pub fn new(backend: &DwBackendConfig, display: &DwDisplayConfig) -> Self {
+        CommandBackend {
+            command: backend.command.clone(),
+            mode: display.mode,
+            output: display.output.clone(),
+        }
+    }
+
+    /// Substitutes the placeholders in one pass, so text coming from the path
+    /// itself (say, a file named "{mode}.png") is never expanded again.
+    fn expand(&self, argument: &str, path: &Path) -> String {
+        let values = [
+            ("{path}", path.to_string_lossy().to_string()),
+            ("{uri}", file_uri(path)),
+            ("{output}", self.output.clone().unwrap_or_default()),
+            ("{mode}", self.mode.as_str().to_string()),
+        ];
+
+        let mut expanded = String::new();
+        let mut rest = argument;
+
+        while !rest.is_empty() {
+            match values
+                .iter()
+                .find(|(placeholder, _)| rest.starts_with(placeholder))
+            {
+                Some((placeholder, value)) => {
+                    expanded.push_str(value);
+                    rest = &rest[placeholder.len()..];
+                }
+                None => {
+                    let next = rest.chars().next().unwrap();
+                    expanded.push(next);
+                    rest = &rest[next.len_utf8()..];
+                }
+            }
+        }
+
+        expanded
+    }
+}
+
+impl WallpaperBackend for CommandBackend {
+    fn name(&self) -> &'static str {
+        "command"
+    }
+
+    fn detect(&self) -> bool {
+        !self.command.is_empty()
+    }
+
+    fn set(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let argv: Vec<String> = self
+            .command
+            .iter()
+            .map(|argument| self.expand(argument, path))
+            .collect();
+        let (program, args) = argv
+            .split_first()
+            .ok_or("Error: The backend command is empty.")?;
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+        run(program, &args)
+    }
+
+    fn supports_outputs(&self) -> bool {
+        self.command.iter().any(|arg| arg.contains("{output}"))
+    }
+
+    fn programs(&self) -> Vec<&str> {
+        self.command.iter().take(1).map(String::as_str).collect()
+    }
+
+    fn current(&self) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        Ok(None)
+    }
+}
@@ -0,0 +1,87 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// src/backends/gnome.rs
+
+use super::{
+    file_uri, gsettings::picture_options, is_desktop, path_from_uri, query, run, WallpaperBackend,
+};
+use crate::color_scheme::prefers_dark;
+use crate::core_models::{DwDisplayConfig, DwFillMode};
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+const SCHEMA: &str = "org.gnome.desktop.background";
+
+/// GNOME keeps one image per color scheme (`picture-uri` and `picture-uri-dark`)
+/// and shows the one matching `org.gnome.desktop.interface color-scheme`.
+pub struct GnomeBackend {
+    mode: DwFillMode,
+}
+
+impl GnomeBackend {
+    pub fn new(display: &DwDisplayConfig) -> Self {
+        GnomeBackend { mode: display.mode }
+    }
+}
+
+impl WallpaperBackend for GnomeBackend {
+    fn name(&self) -> &'static str {
+        "gnome"
+    }
+
+    fn detect(&self) -> bool {
+        is_desktop("gnome")
+    }
+
+    fn set(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        self.set_themed(path, path)
+    }
+
+    fn set_themed(&self, light: &Path, dark: &Path) -> Result<(), Box<dyn Error>> {
+        run(
+            "gsettings",
+            &["set", SCHEMA, "picture-uri", &file_uri(light)],
+        )?;
+        run(
+            "gsettings",
+            &["set", SCHEMA, "picture-uri-dark", &file_uri(dark)],
+        )?;
+        run(
+            "gsettings",
+            &["set", SCHEMA, "picture-options", picture_options(self.mode)],
+        )
+    }
+
+    fn programs(&self) -> Vec<&str> {
+        vec!["gsettings"]
+    }
+
+    fn dbus_services(&self) -> Vec<&str> {
+        vec!["ca.desrt.dconf"]
+    }
+
+    fn current(&self) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        let key = if prefers_dark() {
+            "picture-uri-dark"
+        } else {
+            "picture-uri"
+        };
+        let value = query("gsettings", &["get", SCHEMA, key])?;
+
+        Ok(path_from_uri(value.trim_matches('\'')))
+    }
+}
@@ -0,0 +1,127 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// src/backends/gsettings.rs
+
+use super::{file_uri, is_desktop, path_from_uri, query, run, WallpaperBackend};
+use crate::core_models::{DwDisplayConfig, DwFillMode};
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+/// Backend for the GTK desktops that keep their background in a gsettings schema.
+pub struct GsettingsBackend {
+    name: &'static str,
+    desktop: &'static str,
+    schema: &'static str,
+    key: &'static str,
+    // Whether `key` holds a `file://` URI rather than a plain path.
+    uri: bool,
+    mode: DwFillMode,
+}
+
+impl GsettingsBackend {
+    pub fn cinnamon(display: &DwDisplayConfig) -> Self {
+        GsettingsBackend {
+            name: "cinnamon",
+            desktop: "cinnamon",
+            schema: "org.cinnamon.desktop.background",
+            key: "picture-uri",
+            uri: true,
+            mode: display.mode,
+        }
+    }
+
+    pub fn mate(display: &DwDisplayConfig) -> Self {
+        GsettingsBackend {
+            name: "mate",
+            desktop: "mate",
+            schema: "org.mate.background",
+            key: "picture-filename",
+            uri: false,
+            mode: display.mode,
+        }
+    }
+
+    pub fn budgie(display: &DwDisplayConfig) -> Self {
+        GsettingsBackend {
+            name: "budgie",
+            desktop: "budgie",
+            schema: "org.gnome.desktop.background",
+            key: "picture-uri",
+            uri: true,
+            mode: display.mode,
+        }
+    }
+}
+
+/// Value of the `picture-options` key shared by the GNOME-derived schemas.
+pub fn picture_options(mode: DwFillMode) -> &'static str {
+    match mode {
+        DwFillMode::Fill => "zoom",
+        DwFillMode::Fit => "scaled",
+        DwFillMode::Stretch => "stretched",
+        DwFillMode::Center => "centered",
+        DwFillMode::Tile => "wallpaper",
+    }
+}
+
+impl WallpaperBackend for GsettingsBackend {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn detect(&self) -> bool {
+        is_desktop(self.desktop)
+    }
+
+    fn set(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let value = if self.uri {
+            file_uri(path)
+        } else {
+            path.to_str().ok_or("Invalid path")?.to_string()
+        };
+
+        run("gsettings", &["set", self.schema, self.key, &value])?;
+        run(
+            "gsettings",
+            &[
+                "set",
+                self.schema,
+                "picture-options",
+                picture_options(self.mode),
+            ],
+        )
+    }
+
+    fn programs(&self) -> Vec<&str> {
+        vec!["gsettings"]
+    }
+
+    fn current(&self) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        let value = query("gsettings", &["get", self.schema, self.key])?;
+        let value = value.trim_matches('\'');
+
+        if value.is_empty() {
+            return Ok(None);
+        }
+
+        if self.uri {
+            Ok(path_from_uri(value))
+        } else {
+            Ok(Some(PathBuf::from(value)))
+        }
+    }
+}
@@ -0,0 +1,118 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// src/backends/hyprland.rs
+
+use super::{is_desktop, query, run, WallpaperBackend};
+use crate::core_functions::is_dry_run;
+use crate::core_models::{DwDisplayConfig, DwFillMode};
+use std::{
+    env,
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+/// Sets the background through hyprpaper's IPC (`hyprctl hyprpaper ...`).
+pub struct HyprlandBackend {
+    mode: DwFillMode,
+    output: Option<String>,
+}
+
+impl HyprlandBackend {
+    pub fn new(display: &DwDisplayConfig) -> Self {
+        HyprlandBackend {
+            mode: display.mode,
+            output: display.output.clone(),
+        }
+    }
+
+    fn hyprpaper(args: &[&str]) -> Result<(), Box<dyn Error>> {
+        let mut hyprctl_args = vec!["hyprpaper"];
+        hyprctl_args.extend_from_slice(args);
+
+        if is_dry_run() {
+            return run("hyprctl", &hyprctl_args);
+        }
+
+        let reply = query("hyprctl", &hyprctl_args)?;
+        if reply != "ok" {
+            return Err(format!("Error: hyprpaper {} failed: {}", args[0], reply).into());
+        }
+
+        Ok(())
+    }
+}
+
+impl WallpaperBackend for HyprlandBackend {
+    fn name(&self) -> &'static str {
+        "hyprland"
+    }
+
+    fn detect(&self) -> bool {
+        is_desktop("hyprland") || env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some()
+    }
+
+    fn set(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let path_as_str = path.to_str().ok_or("Invalid path")?;
+        // hyprpaper cuts `monitor,path` at the comma and has no escape for it.
+        if path_as_str.contains(',') {
+            return Err(format!(
+                "Error: hyprpaper can't load {:?}, its path has a comma.",
+                path_as_str
+            )
+            .into());
+        }
+
+        // hyprpaper only understands the "contain" and "tile" modes, the rest is cover.
+        let mode_prefix = match self.mode {
+            DwFillMode::Fit => "contain:",
+            DwFillMode::Tile => "tile:",
+            _ => "",
+        };
+        // An empty monitor name applies the wallpaper to every monitor.
+        let target = format!(
+            "{},{}{}",
+            self.output.as_deref().unwrap_or(""),
+            mode_prefix,
+            path_as_str
+        );
+
+        HyprlandBackend::hyprpaper(&["preload", path_as_str])?;
+        HyprlandBackend::hyprpaper(&["wallpaper", &target])?;
+        // Drops every preloaded image no monitor is showing anymore.
+        HyprlandBackend::hyprpaper(&["unload", "unused"])
+    }
+
+    fn supports_outputs(&self) -> bool {
+        true
+    }
+
+    fn programs(&self) -> Vec<&str> {
+        vec!["hyprctl"]
+    }
+
+    fn current(&self) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        let active = query("hyprctl", &["hyprpaper", "listactive"])?;
+
+        // Each line looks like "DP-1 = /path/to/image.png".
+        Ok(active
+            .lines()
+            .filter_map(|line| line.split_once(" = "))
+            .find(|(monitor, _)| match &self.output {
+                Some(output) => monitor.trim() == output,
+                None => true,
+            })
+            .map(|(_, path)| PathBuf::from(path.trim())))
+    }
+}
@@ -0,0 +1,213 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// src/backends/kde.rs
+
+use super::{file_uri, find_in_path, is_desktop, path_from_uri, query, run, WallpaperBackend};
+use crate::core_models::{DwDisplayConfig, DwFillMode};
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+/// D-Bus clients able to call `org.kde.PlasmaShell.evaluateScript`, by preference.
+/// Plasma 6 ships `qdbus6` (or `qdbus-qt6`), Plasma 5 ships `qdbus`.
+const DBUS_CLIENTS: [&str; 6] = [
+    "qdbus6",
+    "qdbus-qt6",
+    "qdbus",
+    "qdbus-qt5",
+    "gdbus",
+    "dbus-send",
+];
+
+/// Sets the wallpaper of the Plasma desktop containments.
+///
+/// The wallpaper is applied through a PlasmaShell script sent over D-Bus, which
+/// allows targeting a single screen. When no D-Bus client is installed the backend
+/// falls back to `plasma-apply-wallpaperimage`, which always covers every screen.
+pub struct KdeBackend {
+    mode: DwFillMode,
+    output: Option<String>,
+}
+
+impl KdeBackend {
+    pub fn new(display: &DwDisplayConfig) -> Self {
+        KdeBackend {
+            mode: display.mode,
+            output: display.output.clone(),
+        }
+    }
+
+    /// Value of the `FillMode` key of the org.kde.image plugin.
+    fn fill_mode(&self) -> u8 {
+        match self.mode {
+            DwFillMode::Stretch => 0,
+            DwFillMode::Fit => 1,
+            DwFillMode::Fill => 2,
+            DwFillMode::Tile => 3,
+            DwFillMode::Center => 6,
+        }
+    }
+
+    /// Screen index targeted by the configured output, `-1` for every screen.
+    fn screen(&self) -> Result<i32, Box<dyn Error>> {
+        match &self.output {
+            Some(output) => output.parse().map_err(|_| {
+                format!(
+                    "Error: KDE screens are selected by index, got {:?}.",
+                    output
+                )
+                .into()
+            }),
+            None => Ok(-1),
+        }
+    }
+
+    /// Arguments making `client` run `script` in PlasmaShell.
+    fn script_args(client: &str, script: &str) -> Vec<String> {
+        match client {
+            "gdbus" => vec![
+                "call".to_string(),
+                "--session".to_string(),
+                "--dest".to_string(),
+                "org.kde.plasmashell".to_string(),
+                "--object-path".to_string(),
+                "/PlasmaShell".to_string(),
+                "--method".to_string(),
+                "org.kde.PlasmaShell.evaluateScript".to_string(),
+                script.to_string(),
+            ],
+            "dbus-send" => vec![
+                "--session".to_string(),
+                "--print-reply=literal".to_string(),
+                "--dest=org.kde.plasmashell".to_string(),
+                "/PlasmaShell".to_string(),
+                "org.kde.PlasmaShell.evaluateScript".to_string(),
+                format!("string:{}", script),
+            ],
+            _ => vec![
+                "org.kde.plasmashell".to_string(),
+                "/PlasmaShell".to_string(),
+                "org.kde.PlasmaShell.evaluateScript".to_string(),
+                script.to_string(),
+            ],
+        }
+    }
+
+    /// Runs `script` in PlasmaShell and returns what it printed.
+    fn evaluate_script(client: &str, script: &str) -> Result<String, Box<dyn Error>> {
+        let args = KdeBackend::script_args(client, script);
+        let reply = query(client, &args.iter().map(String::as_str).collect::<Vec<_>>())?;
+
+        // gdbus wraps the reply in a GVariant tuple: ('...',)
+        let reply = reply
+            .strip_prefix("('")
+            .and_then(|reply| reply.strip_suffix("',)"))
+            .map(|reply| reply.replace("\\n", "\n"))
+            .unwrap_or(reply);
+
+        Ok(reply.trim().to_string())
+    }
+
+    fn dbus_client() -> Option<&'static str> {
+        DBUS_CLIENTS
+            .into_iter()
+            .find(|client| find_in_path(client).is_some())
+    }
+}
+
+impl WallpaperBackend for KdeBackend {
+    fn name(&self) -> &'static str {
+        "kde"
+    }
+
+    fn detect(&self) -> bool {
+        is_desktop("kde")
+    }
+
+    fn set(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let screen = self.screen()?;
+
+        let client = match KdeBackend::dbus_client() {
+            Some(client) => client,
+            None if screen < 0 && find_in_path("plasma-apply-wallpaperimage").is_some() => {
+                let fill_mode = match self.mode {
+                    DwFillMode::Stretch => "stretch",
+                    DwFillMode::Fit => "preserveAspectFit",
+                    DwFillMode::Fill => "preserveAspectCrop",
+                    DwFillMode::Tile => "tile",
+                    DwFillMode::Center => "pad",
+                };
+                let path_as_str = path.to_str().ok_or("Invalid path")?;
+
+                return run(
+                    "plasma-apply-wallpaperimage",
+                    &["--fill-mode", fill_mode, path_as_str],
+                );
+            }
+            None => return Err("Error: No D-Bus client found to reach PlasmaShell.".into()),
+        };
+
+        // The URI is embedded as a JSON string literal, which is also a valid
+        // JavaScript one, so no character of the path can end the string early.
+        let script = format!(
+            "var uri = {}; var screen = {}; var Desktops = desktops(); for (i=0; i<Desktops.length; i++) {{d = Desktops[i]; if (screen >= 0 && d.screen != screen) continue; d.wallpaperPlugin = \"org.kde.image\";d.currentConfigGroup = Array(\"Wallpaper\", \"org.kde.image\", \"General\");d.writeConfig(\"Image\", uri);d.writeConfig(\"FillMode\", {})}}",
+            serde_json::to_string(&file_uri(path))?,
+            screen,
+            self.fill_mode()
+        );
+
+        let args = KdeBackend::script_args(client, &script);
+        run(client, &args.iter().map(String::as_str).collect::<Vec<_>>())
+    }
+
+    fn supports_outputs(&self) -> bool {
+        true
+    }
+
+    // Plasma numbers its screens instead.
+    fn names_outputs(&self) -> bool {
+        false
+    }
+
+    fn programs(&self) -> Vec<&str> {
+        let mut programs = DBUS_CLIENTS.to_vec();
+        programs.push("plasma-apply-wallpaperimage");
+        programs
+    }
+
+    fn dbus_services(&self) -> Vec<&str> {
+        vec!["org.kde.plasmashell"]
+    }
+
+    fn current(&self) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        let client = match KdeBackend::dbus_client() {
+            Some(client) => client,
+            None => return Ok(None),
+        };
+
+        let script = format!(
+            "var screen = {}; var Desktops = desktops(); for (i=0; i<Desktops.length; i++) {{d = Desktops[i]; if (screen >= 0 && d.screen != screen) continue; d.currentConfigGroup = Array(\"Wallpaper\", \"org.kde.image\", \"General\"); print(d.readConfig(\"Image\")); break;}}",
+            self.screen()?
+        );
+        let value = KdeBackend::evaluate_script(client, &script)?;
+
+        if value.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(path_from_uri(&value).or_else(|| Some(PathBuf::from(value))))
+    }
+}
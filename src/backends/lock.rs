@@ -0,0 +1,291 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// src/backends/lock.rs
+
+use super::{
+    file_uri, find_in_path, gsettings::picture_options, is_desktop, path_from_uri, query, run,
+    WallpaperBackend,
+};
+use crate::core_functions::is_dry_run;
+use crate::core_models::{DwDisplayConfig, DwFillMode};
+use std::{
+    env,
+    error::Error,
+    fs,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+};
+
+const GNOME_SCHEMA: &str = "org.gnome.desktop.screensaver";
+
+/// Path of the kscreenlocker image settings, as `--group` arguments.
+const KDE_GROUPS: [&str; 8] = [
+    "--group",
+    "Greeter",
+    "--group",
+    "Wallpaper",
+    "--group",
+    "org.kde.image",
+    "--group",
+    "General",
+];
+
+/// Generated config, meant to be used with `swaylock -C ~/.dwr/state/swaylock/config`.
+const SWAYLOCK_CONFIG: &str = "state/swaylock/config";
+
+/// Sets the GNOME lock screen through `org.gnome.desktop.screensaver`.
+pub struct GnomeLockBackend {
+    mode: DwFillMode,
+}
+
+impl GnomeLockBackend {
+    pub fn new(display: &DwDisplayConfig) -> Self {
+        GnomeLockBackend { mode: display.mode }
+    }
+}
+
+impl WallpaperBackend for GnomeLockBackend {
+    fn name(&self) -> &'static str {
+        "gnome-lock"
+    }
+
+    fn detect(&self) -> bool {
+        is_desktop("gnome")
+    }
+
+    fn set(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        run(
+            "gsettings",
+            &["set", GNOME_SCHEMA, "picture-uri", &file_uri(path)],
+        )?;
+        run(
+            "gsettings",
+            &[
+                "set",
+                GNOME_SCHEMA,
+                "picture-options",
+                picture_options(self.mode),
+            ],
+        )
+    }
+
+    fn programs(&self) -> Vec<&str> {
+        vec!["gsettings"]
+    }
+
+    fn current(&self) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        let value = query("gsettings", &["get", GNOME_SCHEMA, "picture-uri"])?;
+
+        Ok(path_from_uri(value.trim_matches('\'')))
+    }
+}
+
+/// Writes the Plasma lock screen image in `kscreenlockerrc`.
+pub struct KdeLockBackend {
+    mode: DwFillMode,
+}
+
+impl KdeLockBackend {
+    pub fn new(display: &DwDisplayConfig) -> Self {
+        KdeLockBackend { mode: display.mode }
+    }
+
+    /// Plasma 6 ships `kwriteconfig6`, Plasma 5 `kwriteconfig5`.
+    fn tool(prefix: &str) -> String {
+        ["6", "5"]
+            .iter()
+            .map(|version| format!("{}{}", prefix, version))
+            .find(|tool| find_in_path(tool).is_some())
+            .unwrap_or_else(|| format!("{}6", prefix))
+    }
+}
+
+impl WallpaperBackend for KdeLockBackend {
+    fn name(&self) -> &'static str {
+        "kde-lock"
+    }
+
+    fn detect(&self) -> bool {
+        is_desktop("kde")
+    }
+
+    fn set(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let tool = KdeLockBackend::tool("kwriteconfig");
+        // Same FillMode values as the desktop's org.kde.image plugin.
+        let fill_mode = match self.mode {
+            DwFillMode::Stretch => "0",
+            DwFillMode::Fit => "1",
+            DwFillMode::Fill => "2",
+            DwFillMode::Tile => "3",
+            DwFillMode::Center => "6",
+        };
+        let uri = file_uri(path);
+
+        run(
+            &tool,
+            &[
+                "--file",
+                "kscreenlockerrc",
+                "--group",
+                "Greeter",
+                "--key",
+                "WallpaperPlugin",
+                "org.kde.image",
+            ],
+        )?;
+        for (key, value) in [("Image", uri.as_str()), ("FillMode", fill_mode)] {
+            let mut args = vec!["--file", "kscreenlockerrc"];
+            args.extend_from_slice(&KDE_GROUPS);
+            args.extend_from_slice(&["--key", key, value]);
+            run(&tool, &args)?;
+        }
+
+        Ok(())
+    }
+
+    fn programs(&self) -> Vec<&str> {
+        vec!["kwriteconfig6", "kwriteconfig5"]
+    }
+
+    fn current(&self) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        let mut args = vec!["--file", "kscreenlockerrc"];
+        args.extend_from_slice(&KDE_GROUPS);
+        args.extend_from_slice(&["--key", "Image"]);
+        let value = query(&KdeLockBackend::tool("kreadconfig"), &args)?;
+
+        if value.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(path_from_uri(&value).or_else(|| Some(PathBuf::from(value))))
+    }
+}
+
+/// Keeps a swaylock config pointing at the lock screen image, for sway and the
+/// other wlroots compositors.
+pub struct SwaylockBackend {
+    mode: DwFillMode,
+}
+
+impl SwaylockBackend {
+    pub fn new(display: &DwDisplayConfig) -> Self {
+        SwaylockBackend { mode: display.mode }
+    }
+
+    fn config_path() -> Result<PathBuf, Box<dyn Error>> {
+        Ok(env::current_dir()?.join(SWAYLOCK_CONFIG))
+    }
+}
+
+impl WallpaperBackend for SwaylockBackend {
+    fn name(&self) -> &'static str {
+        "swaylock"
+    }
+
+    fn detect(&self) -> bool {
+        is_desktop("sway")
+            || is_desktop("hyprland")
+            || env::var_os("SWAYSOCK").is_some()
+            || env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some()
+    }
+
+    fn set(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let path_as_str = path.to_str().ok_or("Invalid path")?;
+        // The config has one option per line, a newline would start another one.
+        if path_as_str.contains('\n') {
+            return Err("Error: swaylock can't use a path with a line break.".into());
+        }
+
+        let config_path = SwaylockBackend::config_path()?;
+        let config = format!("image={}\nscaling={}\n", path_as_str, self.mode.as_str());
+
+        if is_dry_run() {
+            println!("[dry-run] would write {}:", config_path.display());
+            print!("{}", config);
+            return Ok(());
+        }
+
+        if let Some(dir) = config_path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Error: Failed to create {}: {}", dir.display(), e))?;
+        }
+        fs::write(&config_path, config)
+            .map_err(|e| format!("Error: Failed to write {}: {}", config_path.display(), e).into())
+    }
+
+    fn programs(&self) -> Vec<&str> {
+        vec!["swaylock"]
+    }
+
+    fn current(&self) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        let config = match fs::read_to_string(SwaylockBackend::config_path()?) {
+            Ok(config) => config,
+            Err(_) => return Ok(None),
+        };
+
+        Ok(config
+            .lines()
+            .find_map(|line| line.strip_prefix("image="))
+            .map(PathBuf::from))
+    }
+}
+
+/// Copies the image over the background file of the LightDM GTK greeter.
+///
+/// The greeter runs as its own user and can't read our home, so the image is
+/// copied to a file it can read, which `background=` in
+/// `/etc/lightdm/lightdm-gtk-greeter.conf` has to point at.
+pub struct LightdmGreeterBackend {
+    file: Option<String>,
+}
+
+impl LightdmGreeterBackend {
+    pub fn new(file: Option<&String>) -> Self {
+        LightdmGreeterBackend {
+            file: file.cloned(),
+        }
+    }
+}
+
+impl WallpaperBackend for LightdmGreeterBackend {
+    fn name(&self) -> &'static str {
+        "lightdm-gtk-greeter"
+    }
+
+    fn detect(&self) -> bool {
+        true
+    }
+
+    fn set(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let file = self.file.as_ref().ok_or(
+            "Error: Set \"file\" in the greeter target to the background file of the greeter.",
+        )?;
+
+        if is_dry_run() {
+            println!("[dry-run] would copy {} to {}", path.display(), file);
+            return Ok(());
+        }
+
+        fs::copy(path, file)
+            .map_err(|e| format!("Error: Failed to copy the image to {}: {}", file, e))?;
+        // The copy keeps the permissions of the image, which may be private.
+        fs::set_permissions(file, fs::Permissions::from_mode(0o644))
+            .map_err(|e| format!("Error: Failed to make {} readable: {}", file, e).into())
+    }
+
+    fn current(&self) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        Ok(None)
+    }
+}
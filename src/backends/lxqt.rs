@@ -0,0 +1,112 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// src/backends/lxqt.rs
+
+use super::{find_in_path, is_desktop, run, WallpaperBackend};
+use crate::core_models::{DwDisplayConfig, DwFillMode};
+use std::{
+    env,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Sets the background through the desktop file manager of LXQt (`pcmanfm-qt`)
+/// or LXDE (`pcmanfm`).
+pub struct LxqtBackend {
+    mode: DwFillMode,
+}
+
+impl LxqtBackend {
+    pub fn new(display: &DwDisplayConfig) -> Self {
+        LxqtBackend { mode: display.mode }
+    }
+
+    /// The file manager to use, preferring the one that belongs to the running desktop.
+    fn file_manager() -> Option<&'static str> {
+        let preferred = if is_desktop("lxde") {
+            ["pcmanfm", "pcmanfm-qt"]
+        } else {
+            ["pcmanfm-qt", "pcmanfm"]
+        };
+
+        preferred
+            .into_iter()
+            .find(|program| find_in_path(program).is_some())
+    }
+}
+
+impl WallpaperBackend for LxqtBackend {
+    fn name(&self) -> &'static str {
+        "lxqt"
+    }
+
+    fn detect(&self) -> bool {
+        is_desktop("lxqt") || is_desktop("lxde")
+    }
+
+    fn set(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let path_as_str = path.to_str().ok_or("Invalid path")?;
+        let file_manager = LxqtBackend::file_manager()
+            .ok_or("Error: Neither pcmanfm-qt nor pcmanfm was found in PATH.")?;
+
+        let mode = match self.mode {
+            DwFillMode::Fill if file_manager == "pcmanfm" => "crop",
+            DwFillMode::Fill => "zoom",
+            DwFillMode::Fit => "fit",
+            DwFillMode::Stretch => "stretch",
+            DwFillMode::Center => "center",
+            DwFillMode::Tile => "tile",
+        };
+
+        run(
+            file_manager,
+            &[
+                &format!("--set-wallpaper={}", path_as_str),
+                &format!("--wallpaper-mode={}", mode),
+            ],
+        )
+    }
+
+    fn programs(&self) -> Vec<&str> {
+        vec!["pcmanfm-qt", "pcmanfm"]
+    }
+
+    fn current(&self) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        let home = match env::var_os("HOME") {
+            Some(home) => PathBuf::from(home),
+            None => return Ok(None),
+        };
+
+        let (settings, key) = match LxqtBackend::file_manager() {
+            Some("pcmanfm-qt") => (
+                home.join(".config/pcmanfm-qt/lxqt/settings.conf"),
+                "Wallpaper=",
+            ),
+            Some(_) => (
+                home.join(".config/pcmanfm/LXDE/desktop-items-0.conf"),
+                "wallpaper=",
+            ),
+            None => return Ok(None),
+        };
+
+        Ok(fs::read_to_string(settings).ok().and_then(|contents| {
+            contents
+                .lines()
+                .find_map(|line| line.strip_prefix(key))
+                .map(PathBuf::from)
+        }))
+    }
+}
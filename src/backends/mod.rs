@@ -0,0 +1,509 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// src/backends/mod.rs
+
+mod command;
+#[cfg(target_os = "linux")]
+mod gnome;
+#[cfg(target_os = "linux")]
+mod gsettings;
+#[cfg(target_os = "linux")]
+mod hyprland;
+#[cfg(target_os = "linux")]
+mod kde;
+#[cfg(target_os = "linux")]
+mod lock;
+#[cfg(target_os = "linux")]
+mod lxqt;
+mod recording;
+#[cfg(target_os = "linux")]
+mod sway;
+#[cfg(target_os = "windows")]
+mod windows;
+#[cfg(target_os = "linux")]
+mod x11;
+#[cfg(target_os = "linux")]
+mod xfce;
+
+pub use command::CommandBackend;
+pub use recording::RecordingBackend;
+
+use crate::core_functions::is_dry_run;
+use crate::core_models::{DwDisplayConfig, DwTargetConfig, DwTargetKind};
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// A way of applying a wallpaper on a given desktop.
+///
+/// Implementations are tried in registration order by [`BackendRegistry`], and the
+/// first one whose `detect` returns `true` is used.
+pub trait WallpaperBackend {
+    /// Short identifier used in messages (e.g. "gnome").
+    fn name(&self) -> &'static str;
+
+    /// Returns `true` if this backend can drive the current session.
+    fn detect(&self) -> bool;
+
+    /// Applies `path` as the desktop wallpaper.
+    fn set(&self, path: &Path) -> Result<(), Box<dyn Error>>;
+
+    /// Applies separate images for the light and dark color schemes.
+    ///
+    /// Backends without that distinction apply the one matching the current
+    /// color scheme, and have to be called again when it changes.
+    fn set_themed(&self, light: &Path, dark: &Path) -> Result<(), Box<dyn Error>> {
+        #[cfg(target_os = "linux")]
+        if crate::color_scheme::prefers_dark() {
+            return self.set(dark);
+        }
+        #[cfg(not(target_os = "linux"))]
+        let _ = dark;
+
+        self.set(light)
+    }
+
+    /// Returns the wallpaper currently applied, if the backend can tell.
+    fn current(&self) -> Result<Option<PathBuf>, Box<dyn Error>>;
+
+    /// Whether the configured output can be given its own wallpaper. Backends
+    /// returning `false` always cover every output.
+    fn supports_outputs(&self) -> bool {
+        false
+    }
+
+    /// Whether outputs are given by the names `dw outputs` lists, like "DP-1",
+    /// which spanning needs to set each slice.
+    fn names_outputs(&self) -> bool {
+        self.supports_outputs()
+    }
+
+    /// Programs this backend can use to set the wallpaper, listed by `dw backends`.
+    fn programs(&self) -> Vec<&str> {
+        Vec::new()
+    }
+
+    /// D-Bus session services this backend talks to, listed by `dw backends`.
+    fn dbus_services(&self) -> Vec<&str> {
+        Vec::new()
+    }
+}
+
+pub struct BackendRegistry {
+    backends: Vec<Box<dyn WallpaperBackend>>,
+}
+
+impl BackendRegistry {
+    pub fn new() -> Self {
+        BackendRegistry {
+            backends: Vec::new(),
+        }
+    }
+
+    /// Registry with every built-in backend for the current platform.
+    #[cfg_attr(target_os = "windows", allow(unused_variables))]
+    pub fn with_defaults(display: &DwDisplayConfig) -> Self {
+        let mut registry = BackendRegistry::new();
+
+        #[cfg(target_os = "linux")]
+        {
+            // GNOME derivatives list "GNOME" after their own name, so they go first.
+            registry.register(Box::new(gsettings::GsettingsBackend::cinnamon(display)));
+            registry.register(Box::new(gsettings::GsettingsBackend::mate(display)));
+            registry.register(Box::new(gsettings::GsettingsBackend::budgie(display)));
+            registry.register(Box::new(gnome::GnomeBackend::new(display)));
+            registry.register(Box::new(kde::KdeBackend::new(display)));
+            registry.register(Box::new(xfce::XfceBackend::new(display)));
+            registry.register(Box::new(lxqt::LxqtBackend::new(display)));
+            registry.register(Box::new(sway::SwayBackend::new(display)));
+            registry.register(Box::new(hyprland::HyprlandBackend::new(display)));
+            registry.register(Box::new(sway::SwaybgBackend::new(display)));
+            registry.register(Box::new(x11::X11Backend::new(display)));
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            registry.register(Box::new(windows::WindowsBackend));
+        }
+
+        registry
+    }
+
+    /// Registry with the built-in backends of a lock screen or greeter `target`.
+    #[cfg_attr(target_os = "windows", allow(unused_variables))]
+    pub fn for_target(target: &DwTargetConfig, display: &DwDisplayConfig) -> Self {
+        let mut registry = BackendRegistry::new();
+
+        #[cfg(target_os = "linux")]
+        match target.target {
+            DwTargetKind::Lockscreen => {
+                registry.register(Box::new(lock::GnomeLockBackend::new(display)));
+                registry.register(Box::new(lock::KdeLockBackend::new(display)));
+                registry.register(Box::new(lock::SwaylockBackend::new(display)));
+            }
+            DwTargetKind::Greeter => {
+                registry.register(Box::new(lock::LightdmGreeterBackend::new(
+                    target.file.as_ref(),
+                )));
+            }
+        }
+
+        registry
+    }
+
+    /// Adds a backend after the ones already registered.
+    pub fn register(&mut self, backend: Box<dyn WallpaperBackend>) {
+        self.backends.push(backend);
+    }
+
+    /// Appends the backends of `other`, keeping their order.
+    pub fn extend(&mut self, other: BackendRegistry) {
+        self.backends.extend(other.backends);
+    }
+
+    /// Returns the first registered backend that detects the current session.
+    pub fn detect(&self) -> Option<&dyn WallpaperBackend> {
+        self.backends
+            .iter()
+            .find(|backend| backend.detect())
+            .map(|backend| backend.as_ref())
+    }
+
+    /// Returns the registered backend called `name`.
+    pub fn find(&self, name: &str) -> Option<&dyn WallpaperBackend> {
+        self.backends
+            .iter()
+            .find(|backend| backend.name() == name)
+            .map(|backend| backend.as_ref())
+    }
+
+    /// The backend named by `forced`, or the detected one when nothing is forced.
+    pub fn select(&self, forced: Option<&str>) -> Result<&dyn WallpaperBackend, Box<dyn Error>> {
+        match forced {
+            Some(name) => self.find(name).ok_or_else(|| {
+                format!(
+                    "Error: Unknown backend {:?}. Run `dw backends` to list them.",
+                    name
+                )
+                .into()
+            }),
+            None => self
+                .detect()
+                .ok_or_else(|| "Error: Unsupported desktop environment.".into()),
+        }
+    }
+
+    /// Human readable account of what every backend found and which one is used.
+    pub fn report(&self, forced: Option<&str>) -> String {
+        let mut report = String::new();
+
+        #[cfg(target_os = "linux")]
+        {
+            report.push_str(&format!(
+                "Desktop environments: {}\n",
+                match desktop_environments().join(", ") {
+                    desktops if desktops.is_empty() => "none".to_string(),
+                    desktops => desktops,
+                }
+            ));
+            for variable in [
+                "XDG_CURRENT_DESKTOP",
+                "XDG_SESSION_DESKTOP",
+                "DESKTOP_SESSION",
+            ] {
+                if let Ok(value) = std::env::var(variable) {
+                    report.push_str(&format!("  {}={}\n", variable, value));
+                }
+            }
+        }
+
+        let services = dbus_session_services();
+        if services.is_none() {
+            report.push_str("D-Bus session services: unknown (neither busctl nor gdbus worked)\n");
+        }
+
+        report.push_str("\nBackends, in detection order:\n");
+        for backend in &self.backends {
+            let programs: Vec<String> = backend
+                .programs()
+                .into_iter()
+                .map(|program| match find_in_path(program) {
+                    Some(path) => format!("{} ({})", program, path.display()),
+                    None => format!("{} (missing)", program),
+                })
+                .collect();
+            let dbus_services: Vec<String> = backend
+                .dbus_services()
+                .into_iter()
+                .map(|service| match &services {
+                    Some(services) if services.iter().any(|name| name == service) => {
+                        format!("{} (running)", service)
+                    }
+                    Some(_) => format!("{} (not running)", service),
+                    None => format!("{} (unknown)", service),
+                })
+                .collect();
+
+            report.push_str(&format!(
+                "  [{}] {}\n",
+                if backend.detect() { "x" } else { " " },
+                backend.name()
+            ));
+            if !programs.is_empty() {
+                report.push_str(&format!("      programs: {}\n", programs.join(", ")));
+            }
+            if !dbus_services.is_empty() {
+                report.push_str(&format!("      services: {}\n", dbus_services.join(", ")));
+            }
+        }
+
+        let selected = match forced {
+            Some(name) => self
+                .find(name)
+                .map(|backend| (backend, "forced with --backend".to_string())),
+            None => self.detect().map(|backend| {
+                (
+                    backend,
+                    "the first backend, in detection order, that matched this session".to_string(),
+                )
+            }),
+        };
+
+        match selected {
+            Some((backend, reason)) => {
+                report.push_str(&format!("\nSelected: {} ({})\n", backend.name(), reason));
+                match backend.current() {
+                    Ok(Some(path)) => {
+                        report.push_str(&format!("Current wallpaper: {}\n", path.display()))
+                    }
+                    Ok(None) => report.push_str("Current wallpaper: unknown\n"),
+                    Err(e) => report.push_str(&format!("Current wallpaper: unknown ({})\n", e)),
+                }
+            }
+            None => match forced {
+                Some(name) => report.push_str(&format!(
+                    "\nSelected: none, no backend is called {:?}\n",
+                    name
+                )),
+                None => report.push_str("\nSelected: none, no backend matched this session\n"),
+            },
+        }
+
+        report
+    }
+}
+
+impl Default for BackendRegistry {
+    fn default() -> Self {
+        BackendRegistry::with_defaults(&DwDisplayConfig::default())
+    }
+}
+
+/// The desktops named by `XDG_CURRENT_DESKTOP` (a colon-separated list, most
+/// specific first), lowercased and with the usual aliases folded into one name
+/// ("X-Cinnamon" is "cinnamon", "plasmawayland" is "kde"...).
+///
+/// `XDG_SESSION_DESKTOP` and `DESKTOP_SESSION` are only read when
+/// `XDG_CURRENT_DESKTOP` is unset, display managers often leave them stale.
+#[cfg(target_os = "linux")]
+pub fn desktop_environments() -> Vec<String> {
+    let mut desktops: Vec<String> = Vec::new();
+
+    for variable in [
+        "XDG_CURRENT_DESKTOP",
+        "XDG_SESSION_DESKTOP",
+        "DESKTOP_SESSION",
+    ] {
+        let value = std::env::var(variable).unwrap_or_default();
+
+        for desktop in value.split(':').map(normalize_desktop) {
+            if !desktop.is_empty() && !desktops.contains(&desktop) {
+                desktops.push(desktop);
+            }
+        }
+
+        if !desktops.is_empty() {
+            break;
+        }
+    }
+
+    desktops
+}
+
+#[cfg(target_os = "linux")]
+fn normalize_desktop(desktop: &str) -> String {
+    let desktop = desktop.trim().to_lowercase();
+    // DESKTOP_SESSION may be a path to the session file.
+    let desktop = desktop.rsplit('/').next().unwrap_or_default();
+
+    match desktop {
+        "x-cinnamon" | "cinnamon-wayland" => "cinnamon",
+        "budgie-desktop" => "budgie",
+        "xfce4" | "xubuntu" => "xfce",
+        "lubuntu" => "lxqt",
+        "mate-session" => "mate",
+        _ if desktop.starts_with("plasma") || desktop.starts_with("kde") => "kde",
+        _ if desktop.starts_with("gnome") => "gnome",
+        _ => desktop,
+    }
+    .to_string()
+}
+
+/// Returns `true` if `desktop` is one of the [`desktop_environments`].
+#[cfg(target_os = "linux")]
+pub fn is_desktop(desktop: &str) -> bool {
+    desktop_environments().iter().any(|name| name == desktop)
+}
+
+/// Names owned on the session bus, or `None` if the bus can't be listed.
+fn dbus_session_services() -> Option<Vec<String>> {
+    let busctl = Command::new("busctl")
+        .args(["--user", "--no-legend", "list"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success());
+
+    if let Some(output) = busctl {
+        return Some(
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter_map(|line| line.split_whitespace().next())
+                .map(String::from)
+                .collect(),
+        );
+    }
+
+    // gdbus replies with a GVariant: (['org.freedesktop.DBus', ':1.0', ...],)
+    let gdbus = Command::new("gdbus")
+        .args([
+            "call",
+            "--session",
+            "--dest",
+            "org.freedesktop.DBus",
+            "--object-path",
+            "/org/freedesktop/DBus",
+            "--method",
+            "org.freedesktop.DBus.ListNames",
+        ])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+
+    Some(
+        String::from_utf8_lossy(&gdbus.stdout)
+            .split('\'')
+            .skip(1)
+            .step_by(2)
+            .map(String::from)
+            .collect(),
+    )
+}
+
+/// Converts `path` into a percent-encoded `file://` URI.
+pub fn file_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    uri
+}
+
+/// Inverse of [`file_uri`]; returns `None` for anything that isn't a `file://` URI.
+#[cfg(target_os = "linux")]
+fn path_from_uri(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?.as_bytes();
+    let mut decoded = Vec::with_capacity(encoded.len());
+    let mut i = 0;
+
+    while i < encoded.len() {
+        let escaped = encoded
+            .get(i + 1..i + 3)
+            .filter(|_| encoded[i] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(encoded[i]);
+                i += 1;
+            }
+        }
+    }
+
+    Some(PathBuf::from(String::from_utf8_lossy(&decoded).to_string()))
+}
+
+/// Runs `program` with `args` and fails if it exits unsuccessfully.
+///
+/// In dry-run mode the command is only printed.
+pub(crate) fn run(program: &str, args: &[&str]) -> Result<(), Box<dyn Error>> {
+    if is_dry_run() {
+        println!("[dry-run] would run: {} {:?}", program, args);
+        return Ok(());
+    }
+
+    let command_execution_output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Error: Failed to execute {}: {}", program, e))?;
+
+    if !command_execution_output.status.success() {
+        return Err(format!(
+            "Error: {} failed: {}",
+            program,
+            String::from_utf8_lossy(&command_execution_output.stderr).trim()
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Looks `program` up in `PATH`.
+pub(crate) fn find_in_path(program: &str) -> Option<PathBuf> {
+    std::env::var_os("PATH").and_then(|paths| {
+        std::env::split_paths(&paths)
+            .map(|dir| dir.join(program))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+/// Runs `program` and returns its trimmed stdout.
+#[cfg(target_os = "linux")]
+pub(crate) fn query(program: &str, args: &[&str]) -> Result<String, Box<dyn Error>> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Error: Failed to execute {}: {}", program, e))?;
+
+    if !output.status.success() {
+        return Err(format!("Error: {} exited with {}", program, output.status).into());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -0,0 +1,53 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// src/backends/recording.rs
+
+use super::WallpaperBackend;
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+/// Catch-all used by `--dry-run` when no real backend matches the session, such
+/// as on CI machines without a desktop. It only reports what would be applied.
+pub struct RecordingBackend;
+
+impl WallpaperBackend for RecordingBackend {
+    fn name(&self) -> &'static str {
+        "recording"
+    }
+
+    fn detect(&self) -> bool {
+        true
+    }
+
+    fn set(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        println!("[dry-run] would set wallpaper: {}", path.display());
+        Ok(())
+    }
+
+    fn set_themed(&self, light: &Path, dark: &Path) -> Result<(), Box<dyn Error>> {
+        println!(
+            "[dry-run] would set wallpaper: {} (dark: {})",
+            light.display(),
+            dark.display()
+        );
+        Ok(())
+    }
+
+    fn current(&self) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        Ok(None)
+    }
+}
@@ -0,0 +1,204 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// src/backends/sway.rs
+
+use super::{find_in_path, is_desktop, run, WallpaperBackend};
+use crate::core_functions::is_dry_run;
+use crate::core_models::{DwDisplayConfig, DwFillMode};
+use std::{
+    env,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+/// `arg` as a single quoted word of a sway command.
+fn sway_quote(arg: &str) -> String {
+    format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Sets the background through sway's own IPC (`swaymsg output <name> bg`).
+pub struct SwayBackend {
+    mode: DwFillMode,
+    output: Option<String>,
+}
+
+impl SwayBackend {
+    pub fn new(display: &DwDisplayConfig) -> Self {
+        SwayBackend {
+            mode: display.mode,
+            output: display.output.clone(),
+        }
+    }
+}
+
+impl WallpaperBackend for SwayBackend {
+    fn name(&self) -> &'static str {
+        "sway"
+    }
+
+    fn detect(&self) -> bool {
+        is_desktop("sway") || env::var_os("SWAYSOCK").is_some()
+    }
+
+    fn set(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let path_as_str = path.to_str().ok_or("Invalid path")?;
+        let output = self.output.as_deref().unwrap_or("*");
+
+        // swaymsg joins its arguments into a command that sway parses again,
+        // where spaces, `;` and `,` in a bare path would split it.
+        run(
+            "swaymsg",
+            &[
+                "output",
+                output,
+                "bg",
+                &sway_quote(path_as_str),
+                self.mode.as_str(),
+            ],
+        )
+    }
+
+    fn supports_outputs(&self) -> bool {
+        true
+    }
+
+    fn programs(&self) -> Vec<&str> {
+        vec!["swaymsg"]
+    }
+
+    fn current(&self) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        Ok(None)
+    }
+}
+
+/// Runs a `swaybg` process for wlroots compositors without a background IPC.
+///
+/// The pid of the spawned process is kept in `./config/swaybg.pid` (or
+/// `swaybg-<output>.pid` for a single output) so the previous instance can be
+/// stopped once the new one is up, without touching the other outputs.
+pub struct SwaybgBackend {
+    mode: DwFillMode,
+    output: Option<String>,
+}
+
+impl SwaybgBackend {
+    pub fn new(display: &DwDisplayConfig) -> Self {
+        SwaybgBackend {
+            mode: display.mode,
+            output: display.output.clone(),
+        }
+    }
+
+    fn pid_file(&self) -> String {
+        match &self.output {
+            Some(output) => format!("./config/swaybg-{}.pid", output),
+            None => "./config/swaybg.pid".to_string(),
+        }
+    }
+
+    fn running_pid(&self) -> Option<u32> {
+        let pid: u32 = fs::read_to_string(self.pid_file())
+            .ok()?
+            .trim()
+            .parse()
+            .ok()?;
+        let comm = fs::read_to_string(format!("/proc/{}/comm", pid)).ok()?;
+
+        if comm.trim() == "swaybg" {
+            Some(pid)
+        } else {
+            None
+        }
+    }
+}
+
+impl WallpaperBackend for SwaybgBackend {
+    fn name(&self) -> &'static str {
+        "swaybg"
+    }
+
+    fn detect(&self) -> bool {
+        env::var_os("WAYLAND_DISPLAY").is_some() && find_in_path("swaybg").is_some()
+    }
+
+    fn set(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if is_dry_run() {
+            let path_as_str = path.to_str().ok_or("Invalid path")?;
+            let mut args = Vec::new();
+            if let Some(output) = &self.output {
+                args.extend_from_slice(&["--output", output.as_str()]);
+            }
+            args.extend_from_slice(&["--image", path_as_str, "--mode", self.mode.as_str()]);
+            return run("swaybg", &args);
+        }
+
+        let previous_pid = self.running_pid();
+
+        let mut command = Command::new("swaybg");
+        if let Some(output) = &self.output {
+            command.args(["--output", output]);
+        }
+
+        let child = command
+            .arg("--image")
+            .arg(path)
+            .args(["--mode", self.mode.as_str()])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Error: Failed to start swaybg: {}", e))?;
+
+        let pid_file = self.pid_file();
+        fs::write(&pid_file, child.id().to_string())
+            .map_err(|e| format!("Error: Failed to write {}: {}", pid_file, e))?;
+
+        // The new instance is started first so the screen never goes blank.
+        if let Some(pid) = previous_pid {
+            run("kill", &[&pid.to_string()])?;
+        }
+
+        Ok(())
+    }
+
+    fn supports_outputs(&self) -> bool {
+        true
+    }
+
+    fn programs(&self) -> Vec<&str> {
+        vec!["swaybg"]
+    }
+
+    fn current(&self) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        let pid = match self.running_pid() {
+            Some(pid) => pid,
+            None => return Ok(None),
+        };
+
+        let cmdline = fs::read(format!("/proc/{}/cmdline", pid))?;
+        let args: Vec<String> = cmdline
+            .split(|byte| *byte == 0)
+            .map(|arg| String::from_utf8_lossy(arg).to_string())
+            .collect();
+
+        Ok(args
+            .iter()
+            .position(|arg| arg == "--image" || arg == "-i")
+            .and_then(|index| args.get(index + 1))
+            .map(PathBuf::from))
+    }
+}
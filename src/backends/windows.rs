@@ -0,0 +1,46 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// src/backends/windows.rs
+
+use super::{run, WallpaperBackend};
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+pub struct WindowsBackend;
+
+impl WallpaperBackend for WindowsBackend {
+    fn name(&self) -> &'static str {
+        "windows"
+    }
+
+    fn detect(&self) -> bool {
+        true
+    }
+
+    fn set(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let path_as_str = path.to_str().ok_or("Invalid path")?;
+
+        run(
+            "external_builds\\windows\\WallpaperChanger.exe",
+            &[path_as_str],
+        )
+    }
+
+    fn current(&self) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        Ok(None)
+    }
+}
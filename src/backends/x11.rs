@@ -0,0 +1,135 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// src/backends/x11.rs
+
+use super::{find_in_path, run, WallpaperBackend};
+use crate::core_models::{DwDisplayConfig, DwFillMode};
+use std::{
+    env,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Standalone setters tried for plain X11 window managers, in order of preference.
+const SETTERS: [&str; 3] = ["feh", "xwallpaper", "nitrogen"];
+
+/// Fallback for X11 window managers (i3, bspwm, awesome, openbox...) that have
+/// no desktop of their own and rely on a standalone setter.
+pub struct X11Backend {
+    mode: DwFillMode,
+    output: Option<String>,
+}
+
+impl X11Backend {
+    pub fn new(display: &DwDisplayConfig) -> Self {
+        X11Backend {
+            mode: display.mode,
+            output: display.output.clone(),
+        }
+    }
+
+    fn setter() -> Option<&'static str> {
+        SETTERS
+            .into_iter()
+            .find(|setter| find_in_path(setter).is_some())
+    }
+}
+
+impl WallpaperBackend for X11Backend {
+    fn name(&self) -> &'static str {
+        "x11"
+    }
+
+    fn detect(&self) -> bool {
+        env::var_os("DISPLAY").is_some() && X11Backend::setter().is_some()
+    }
+
+    fn set(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let path_as_str = path.to_str().ok_or("Invalid path")?;
+        let setter = X11Backend::setter().ok_or("Error: No X11 wallpaper setter found in PATH.")?;
+
+        match setter {
+            "feh" => {
+                let flag = match self.mode {
+                    DwFillMode::Fill => "--bg-fill",
+                    DwFillMode::Fit => "--bg-max",
+                    DwFillMode::Stretch => "--bg-scale",
+                    DwFillMode::Center => "--bg-center",
+                    DwFillMode::Tile => "--bg-tile",
+                };
+                run("feh", &[flag, path_as_str])
+            }
+            "xwallpaper" => {
+                let flag = match self.mode {
+                    DwFillMode::Fill => "--zoom",
+                    DwFillMode::Fit => "--maximize",
+                    DwFillMode::Stretch => "--stretch",
+                    DwFillMode::Center => "--center",
+                    DwFillMode::Tile => "--tile",
+                };
+                let mut args = Vec::new();
+                if let Some(output) = &self.output {
+                    args.extend_from_slice(&["--output", output.as_str()]);
+                }
+                args.extend_from_slice(&[flag, path_as_str]);
+                run("xwallpaper", &args)
+            }
+            _ => {
+                let flag = match self.mode {
+                    DwFillMode::Fill => "--set-zoom-fill",
+                    DwFillMode::Fit => "--set-zoom",
+                    DwFillMode::Stretch => "--set-scaled",
+                    DwFillMode::Center => "--set-centered",
+                    DwFillMode::Tile => "--set-tiled",
+                };
+                run("nitrogen", &[flag, "--save", path_as_str])
+            }
+        }
+    }
+
+    fn programs(&self) -> Vec<&str> {
+        SETTERS.to_vec()
+    }
+
+    fn current(&self) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        let home = match env::var_os("HOME") {
+            Some(home) => PathBuf::from(home),
+            None => return Ok(None),
+        };
+
+        match X11Backend::setter() {
+            // ~/.fehbg ends with: feh --no-fehbg --bg-fill '/path/to/image.png'
+            Some("feh") => Ok(fs::read_to_string(home.join(".fehbg"))
+                .ok()
+                .and_then(|script| {
+                    let line = script.lines().rev().find(|line| line.starts_with("feh "))?;
+                    let path = line.rsplit_once(" '")?.1.trim_end().trim_end_matches('\'');
+                    Some(PathBuf::from(path))
+                })),
+            Some("nitrogen") => Ok(
+                fs::read_to_string(home.join(".config/nitrogen/bg-saved.cfg"))
+                    .ok()
+                    .and_then(|saved| {
+                        saved
+                            .lines()
+                            .find_map(|line| line.strip_prefix("file="))
+                            .map(PathBuf::from)
+                    }),
+            ),
+            _ => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,168 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// src/backends/xfce.rs
+
+use super::{is_desktop, query, run, WallpaperBackend};
+use crate::core_models::{DwDisplayConfig, DwFillMode};
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+const CHANNEL: &str = "xfce4-desktop";
+
+/// Sets the backdrop of every XFCE monitor and workspace, or only the ones
+/// matching the configured output and workspace.
+///
+/// Modern XFCE keeps one `.../monitor<output>/workspace<n>/last-image` property
+/// per monitor and workspace, older releases a single `.../image-path` one.
+pub struct XfceBackend {
+    mode: DwFillMode,
+    output: Option<String>,
+    workspace: Option<u32>,
+}
+
+impl XfceBackend {
+    pub fn new(display: &DwDisplayConfig) -> Self {
+        XfceBackend {
+            mode: display.mode,
+            output: display.output.clone(),
+            workspace: display.workspace,
+        }
+    }
+
+    /// The image properties of the backdrops selected by `output` and `workspace`.
+    fn image_properties(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        let properties = query("xfconf-query", &["--channel", CHANNEL, "--list"])?;
+        let monitor = self
+            .output
+            .as_ref()
+            .map(|output| format!("monitor{}", output));
+        let workspace = self
+            .workspace
+            .map(|workspace| format!("workspace{}", workspace));
+
+        let selected: Vec<String> = properties
+            .lines()
+            .map(str::trim)
+            .filter(|property| {
+                property.ends_with("/last-image") || property.ends_with("/image-path")
+            })
+            .filter(|property| {
+                let segments: Vec<&str> = property.split('/').collect();
+                monitor
+                    .as_ref()
+                    .is_none_or(|monitor| segments.contains(&monitor.as_str()))
+                    && workspace
+                        .as_ref()
+                        .is_none_or(|workspace| segments.contains(&workspace.as_str()))
+            })
+            .map(String::from)
+            .collect();
+
+        if selected.is_empty() {
+            return Err("Error: No matching XFCE backdrop property was found.".into());
+        }
+
+        Ok(selected)
+    }
+}
+
+impl WallpaperBackend for XfceBackend {
+    fn name(&self) -> &'static str {
+        "xfce"
+    }
+
+    fn detect(&self) -> bool {
+        is_desktop("xfce")
+    }
+
+    fn set(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let path_as_str = path.to_str().ok_or("Invalid path")?;
+        let image_style = match self.mode {
+            DwFillMode::Center => "1",
+            DwFillMode::Tile => "2",
+            DwFillMode::Stretch => "3",
+            DwFillMode::Fit => "4",
+            DwFillMode::Fill => "5",
+        };
+
+        for property in self.image_properties()? {
+            run(
+                "xfconf-query",
+                &[
+                    "--channel",
+                    CHANNEL,
+                    "--property",
+                    &property,
+                    "--create",
+                    "--type",
+                    "string",
+                    "--set",
+                    path_as_str,
+                ],
+            )?;
+
+            let backdrop = property
+                .rsplit_once('/')
+                .map_or("", |(backdrop, _)| backdrop);
+            run(
+                "xfconf-query",
+                &[
+                    "--channel",
+                    CHANNEL,
+                    "--property",
+                    &format!("{}/image-style", backdrop),
+                    "--create",
+                    "--type",
+                    "int",
+                    "--set",
+                    image_style,
+                ],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn supports_outputs(&self) -> bool {
+        true
+    }
+
+    fn programs(&self) -> Vec<&str> {
+        vec!["xfconf-query"]
+    }
+
+    fn dbus_services(&self) -> Vec<&str> {
+        vec!["org.xfce.Xfconf"]
+    }
+
+    fn current(&self) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        let property = match self.image_properties()?.into_iter().next() {
+            Some(property) => property,
+            None => return Ok(None),
+        };
+        let value = query(
+            "xfconf-query",
+            &["--channel", CHANNEL, "--property", &property],
+        )?;
+
+        if value.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(PathBuf::from(value)))
+    }
+}
@@ -19,6 +19,18 @@ use clap::{Parser, Subcommand};
 #[derive(Parser, Debug)]
 #[command(name = "dw", about = "Daily Wallpaper Manager")]
 pub struct Cli {
+    #[arg(
+        long,
+        global = true,
+        help = "Print the commands and config changes instead of applying them"
+    )]
+    pub dry_run: bool,
+    #[arg(
+        long,
+        global = true,
+        help = "Use this wallpaper backend instead of detecting one (see `dw backends`)"
+    )]
+    pub backend: Option<String>,
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -44,15 +56,73 @@ pub enum Commands {
             required_if_eq("preset", "by hours")
         )]
         interval: Option<u8>,
+        #[arg(long, help = "Time of day of the day preset's change (HH:MM)")]
+        at: Option<String>,
+    },
+    #[command(about = "Change wallpapers at given times or on a cron/OnCalendar expression")]
+    Schedule {
+        #[arg(
+            long,
+            value_delimiter = ',',
+            help = "Times of day to change at, as in 07:00,12:00,19:00"
+        )]
+        times: Vec<String>,
+        #[arg(
+            long,
+            value_delimiter = ',',
+            help = "Days of the week to change on, as in mon,wed,fri"
+        )]
+        weekdays: Vec<String>,
+        #[arg(
+            long,
+            conflicts_with_all = ["times", "weekdays"],
+            help = "Cron expression, used as is by the cron scheduler"
+        )]
+        cron: Option<String>,
+        #[arg(
+            long,
+            conflicts_with_all = ["times", "weekdays"],
+            help = "OnCalendar expression, used as is by the systemd scheduler"
+        )]
+        on_calendar: Option<String>,
     },
     #[command(about = "Set the first wallpaper in the cycle and reset")]
     Reset,
     #[command(about = "Sets a specific wallpaper, but does not change the cycle")]
-    SetWallpaper { path: String },
+    SetWallpaper {
+        path: String,
+        #[arg(long, help = "Image to use while the desktop is in dark mode")]
+        dark: Option<String>,
+        #[arg(long, help = "Output (or KDE screen index) to apply the wallpaper to")]
+        output: Option<String>,
+    },
     #[command(about = "Set the next wallpaper in the cycle")]
-    Next,
+    Next {
+        #[arg(long, help = "Only move the cycle of this output")]
+        output: Option<String>,
+        #[arg(
+            long,
+            conflicts_with = "output",
+            help = "Move the cycle of this sway/i3 workspace"
+        )]
+        workspace: Option<String>,
+    },
+    #[command(about = "Set the next wallpaper in the cycle if a change is due")]
+    Tick {
+        #[arg(long, help = "Make every change missed since the last one, not just one")]
+        catch_up: bool,
+    },
     #[command(about = "Set the previous wallpaper in the cycle")]
-    Previous,
+    Previous {
+        #[arg(long, help = "Only move the cycle of this output")]
+        output: Option<String>,
+        #[arg(
+            long,
+            conflicts_with = "output",
+            help = "Move the cycle of this sway/i3 workspace"
+        )]
+        workspace: Option<String>,
+    },
     #[command(about = "Disable daily wallpapers")]
     Off,
     #[command(about = "Enable daily wallpapers")]
@@ -65,4 +135,16 @@ pub enum Commands {
     SetConfig { path: String },
     #[command(about = "Performs first time setup")]
     Init,
+    #[command(about = "Shows which wallpaper backend would be used and why")]
+    Backends,
+    #[command(about = "Lists the connected outputs")]
+    Outputs,
+    #[command(about = "Switches the wallpaper with the focused sway/i3 workspace")]
+    WatchWorkspaces,
+    #[command(about = "Applies the dark or light variants again when the color scheme changes")]
+    WatchColorScheme,
+    #[command(
+        about = "Stays resident, changing wallpapers on schedule while enabled and following color scheme changes"
+    )]
+    Daemon,
 }
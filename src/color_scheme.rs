@@ -0,0 +1,150 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// src/color_scheme.rs
+
+use crate::backends::{is_desktop, query};
+use std::{
+    error::Error,
+    io::{BufRead, BufReader},
+    process::{Child, ChildStdout, Command, Stdio},
+};
+
+const PORTAL_ARGS: [&str; 6] = [
+    "--session",
+    "--dest",
+    "org.freedesktop.portal.Desktop",
+    "--object-path",
+    "/org/freedesktop/portal/desktop",
+    "--method",
+];
+
+/// Value of `org.freedesktop.appearance color-scheme` in a gdbus reply, e.g.
+/// `(<<uint32 1>>,)`: 0 is no preference, 1 dark and 2 light.
+fn portal_value(reply: &str) -> Option<u32> {
+    let (_, value) = reply.split_once("uint32 ")?;
+    let digits: String = value.chars().take_while(char::is_ascii_digit).collect();
+
+    digits.parse().ok()
+}
+
+/// What the settings portal says, `None` without a portal or a preference.
+fn portal_prefers_dark() -> Option<bool> {
+    let mut args = PORTAL_ARGS.to_vec();
+    args.extend_from_slice(&[
+        "org.freedesktop.portal.Settings.Read",
+        "org.freedesktop.appearance",
+        "color-scheme",
+    ]);
+
+    match portal_value(&query("gdbus", &args).ok()?)? {
+        1 => Some(true),
+        2 => Some(false),
+        _ => None,
+    }
+}
+
+fn gnome_prefers_dark() -> Option<bool> {
+    let scheme = query(
+        "gsettings",
+        &["get", "org.gnome.desktop.interface", "color-scheme"],
+    )
+    .ok()?;
+
+    Some(scheme.trim_matches('\'') == "prefer-dark")
+}
+
+/// Plasma has no light/dark switch, only color schemes like "BreezeDark".
+fn kde_prefers_dark() -> Option<bool> {
+    let args = [
+        "--file",
+        "kdeglobals",
+        "--group",
+        "General",
+        "--key",
+        "ColorScheme",
+    ];
+    let scheme = ["kreadconfig6", "kreadconfig5"]
+        .iter()
+        .find_map(|tool| query(tool, &args).ok())?;
+
+    Some(scheme.to_lowercase().contains("dark"))
+}
+
+/// Whether the session is in dark mode, asking the settings portal first and
+/// then the desktop itself. Sessions that can't tell are light.
+pub fn prefers_dark() -> bool {
+    portal_prefers_dark()
+        .or_else(|| {
+            if is_desktop("kde") {
+                kde_prefers_dark()
+            } else {
+                gnome_prefers_dark()
+            }
+        })
+        .unwrap_or(false)
+}
+
+/// `gdbus monitor` on the settings portal, with its signals on stdout.
+pub fn monitor_color_scheme() -> Result<Child, Box<dyn Error>> {
+    Command::new("gdbus")
+        .args(["monitor"])
+        .args(&PORTAL_ARGS[..PORTAL_ARGS.len() - 1])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Error: Failed to run gdbus: {}", e).into())
+}
+
+/// Calls `on_change` for every new color scheme announced on `signals`, the
+/// stdout of [`monitor_color_scheme`], until it ends or `on_change` fails.
+pub fn follow_color_scheme<F>(signals: ChildStdout, mut on_change: F) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut() -> Result<(), Box<dyn Error>>,
+{
+    // e.g. `/org/freedesktop/portal/desktop: org.freedesktop.portal.Settings.SettingChanged
+    // ('org.freedesktop.appearance', 'color-scheme', <uint32 1>)`
+    for line in BufReader::new(signals).lines() {
+        let line = line?;
+
+        if !line.contains("SettingChanged")
+            || !line.contains("'org.freedesktop.appearance', 'color-scheme'")
+        {
+            continue;
+        }
+
+        on_change()?;
+    }
+
+    Ok(())
+}
+
+/// Calls `on_change` every time the settings portal announces a new color
+/// scheme, until the session bus goes away or `on_change` fails.
+pub fn watch_color_scheme<F>(on_change: F) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut() -> Result<(), Box<dyn Error>>,
+{
+    let mut child = monitor_color_scheme()?;
+    let stdout = child.stdout.take().ok_or("Error: No signal stream")?;
+
+    if let Err(e) = follow_color_scheme(stdout, on_change) {
+        let _ = child.kill();
+        let _ = child.wait();
+        return Err(e);
+    }
+
+    let status = child.wait()?;
+    Err(format!("Error: The gdbus signal stream ended ({}).", status).into())
+}
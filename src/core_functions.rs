@@ -14,103 +14,248 @@
 
 // src/core.rs
 
-use crate::core_models::{DwConfig, DwPreset, DwTimeConfig, DwWallpaperCandidate};
+use crate::backends::{BackendRegistry, CommandBackend, RecordingBackend};
+use crate::core_models::{
+    DwBackendConfig, DwConfig, DwDisplayConfig, DwPreset, DwTargetConfig, DwTimeConfig,
+    DwWallpaperCandidate,
+};
 use chrono::Local;
 use std::{
+    collections::{BTreeMap, HashSet},
     error::Error,
     fs::{self, File},
     io::Write,
     path::{Path, PathBuf},
-    process::Command
+    process::Command,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
 };
 
+#[cfg(target_os = "windows")]
+use crate::schedule::Schedule;
 #[cfg(target_os = "linux")]
-use std::env;
+use crate::{displays::list_outputs, session::import_session_environment, span::span_slices};
 
-pub fn change_wallpaper(path: &Path) -> Result<(), Box<dyn Error>> {
-    if !path.exists() {
-        return Err("Error: The specified file path does not exist.".into());
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// Makes every desktop command and config write only print what it would do.
+pub fn set_dry_run(enabled: bool) {
+    DRY_RUN.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
+static FORCED_BACKEND: Mutex<Option<String>> = Mutex::new(None);
+
+/// Skips detection and always uses the backend called `name`.
+pub fn set_forced_backend(name: Option<String>) {
+    *FORCED_BACKEND.lock().unwrap() = name;
+}
+
+pub fn forced_backend() -> Option<String> {
+    FORCED_BACKEND.lock().unwrap().clone()
+}
+
+/// Every backend usable with this config, in detection order.
+pub fn build_registry(display: &DwDisplayConfig, backend: Option<&DwBackendConfig>) -> BackendRegistry {
+    let mut registry = BackendRegistry::new();
+    if let Some(backend) = backend {
+        registry.register(Box::new(CommandBackend::new(backend, display)));
+    }
+    registry.extend(BackendRegistry::with_defaults(display));
+    if is_dry_run() {
+        registry.register(Box::new(RecordingBackend));
     }
+    registry
+}
 
-    if tree_magic::from_filepath(path).split('/').next() != Some("image") {
-        return Err("Error: The file is not an image.".into());
+/// Shows `path` on a lock screen or greeter `target`.
+pub fn change_target_wallpaper(
+    target: &DwTargetConfig,
+    path: &Path,
+    dark: Option<&Path>,
+    display: &DwDisplayConfig,
+) -> Result<(), Box<dyn Error>> {
+    for image in Some(path).into_iter().chain(dark) {
+        validate_image(image)?;
     }
 
     #[cfg(target_os = "linux")]
-    {
-        set_linux_wallpaper(path)
+    import_session_environment();
+
+    let mut registry = BackendRegistry::for_target(target, display);
+    if is_dry_run() {
+        registry.register(Box::new(RecordingBackend));
     }
 
-    #[cfg(target_os = "windows")]
-    {
-        set_windows_wallpaper(path)
+    match registry.detect() {
+        Some(backend) => match dark {
+            Some(dark) => backend.set_themed(path, dark),
+            None => backend.set(path),
+        }
+        .map_err(|e| format!("{} backend: {}", backend.name(), e).into()),
+        None => Err(format!(
+            "Error: No {} backend for this session.",
+            target.target.as_str()
+        )
+        .into()),
     }
 }
 
-#[cfg(target_os = "linux")]
-fn set_linux_wallpaper(path: &Path) -> Result<(), Box<dyn Error>> {
+/// Suffixes naming the dark variant of an image, as in `foo-dark.jpg`.
+const DARK_SUFFIXES: [&str; 2] = ["-dark", "_dark"];
 
-    env::set_var("DISPLAY", ":0");
-    env::set_var("XAUTHORITY", "/run/user/1000/.mutter-Xwaylandauth.FJ82G2");
-    env::set_var("DBUS_SESSION_BUS_ADDRESS", "unix:path=/run/user/1000/bus");
+/// The image to show instead of `path` in dark mode: the one declared in
+/// `dark_variants`, or an image next to it named like it plus a dark suffix
+/// (`foo.jpg` and `foo-dark.jpg`, preferably with the same extension).
+pub fn dark_variant(path: &str, dark_variants: &BTreeMap<String, String>) -> Option<String> {
+    if let Some(dark) = dark_variants.get(path) {
+        return Some(dark.clone());
+    }
 
+    let path = Path::new(path);
+    let stem = path.file_stem()?.to_str()?;
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let names: Vec<String> = DARK_SUFFIXES
+        .iter()
+        .map(|suffix| format!("{}{}", stem, suffix))
+        .collect();
 
-    let desktop_env = get_desktop_environment();
+    let mut variants: Vec<PathBuf> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|variant| {
+            variant
+                .file_stem()
+                .and_then(|variant_stem| variant_stem.to_str())
+                .is_some_and(|variant_stem| names.iter().any(|name| name == variant_stem))
+        })
+        .filter(|variant| tree_magic::from_filepath(variant).starts_with("image/"))
+        .collect();
+    variants.sort_by_key(|variant| (variant.extension() != path.extension(), variant.clone()));
 
-    let command = match desktop_env.as_str() {
-        "gnome" => format!("gsettings set org.gnome.desktop.background picture-uri-dark {:?}", path),
-        "kde" => format!("qdbus org.kde.plasmashell /PlasmaShell org.kde.PlasmaShell.evaluateScript 'var Desktops = desktops(); for (i=0; i<Desktops.length; i++) {{d = Desktops[i];d.wallpaperPlugin = \"org.kde.image\";d.currentConfigGroup = Array(\"Wallpaper\", \"org.kde.image\", \"General\");d.writeConfig(\"Image\", \"file://{}\")}}'", path.to_string_lossy()),
-        "xfce" => format!("xfconf-query --channel xfce4-desktop --property /backdrop/screen0/monitor0/image-path --set {:?}", path),
-        _ => return Err("Error: Unsupported Linux desktop environment.".into()),
-    };
+    variants.first().map(|variant| variant.to_string_lossy().to_string())
+}
+
+/// Leaves out of a directory listing the dark variants of the images listed
+/// with them, they are shown in their place instead of taking a turn.
+fn skip_dark_variants<P: AsRef<Path>>(paths: &mut Vec<P>) {
+    let stems: HashSet<String> = paths
+        .iter()
+        .filter_map(|path| path.as_ref().file_stem())
+        .map(|stem| stem.to_string_lossy().to_string())
+        .collect();
 
-    let command_execution_output = Command::new("sh")
-        .args(["-c", &command])
-        .output()
-        .map_err(|e| {
-            format!(
-                "Error: Failed to execute process to change wallpaper: {}",
-                e
-            )
-        })?;
-
-    if !command_execution_output.status.success() {
-        return Err("Error: The command to change the wallpaper failed.".into());
+    paths.retain(|path| {
+        let stem = match path.as_ref().file_stem() {
+            Some(stem) => stem.to_string_lossy().to_string(),
+            None => return true,
+        };
+
+        !DARK_SUFFIXES.iter().any(|suffix| {
+            stem.strip_suffix(suffix)
+                .is_some_and(|light_stem| stems.contains(light_stem))
+        })
+    });
+}
+
+fn validate_image(image: &Path) -> Result<(), Box<dyn Error>> {
+    if !image.exists() {
+        return Err("Error: The specified file path does not exist.".into());
+    }
+
+    if tree_magic::from_filepath(image).split('/').next() != Some("image") {
+        return Err("Error: The file is not an image.".into());
     }
 
     Ok(())
 }
 
-#[cfg(target_os = "linux")]
-fn get_desktop_environment() -> String {
-    if let Ok(desktop) = std::env::var("XDG_CURRENT_DESKTOP") {
-        desktop.to_lowercase()
-    } else if let Ok(desktop) = std::env::var("DESKTOP_SESSION") {
-        desktop.to_lowercase()
-    } else {
-        String::new()
+pub fn change_wallpaper(
+    path: &Path,
+    dark: Option<&Path>,
+    display: &DwDisplayConfig,
+    backend: Option<&DwBackendConfig>,
+) -> Result<(), Box<dyn Error>> {
+    for image in Some(path).into_iter().chain(dark) {
+        validate_image(image)?;
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        import_session_environment();
+
+        if display.span && display.output.is_none() && set_spanned(path, dark, display, backend)? {
+            return Ok(());
+        }
+    }
+
+    let registry = build_registry(display, backend);
+    let backend = registry.select(forced_backend().as_deref())?;
+
+    match dark {
+        Some(dark) => backend.set_themed(path, dark),
+        None => backend.set(path),
     }
+    .map_err(|e| format!("{} backend: {}", backend.name(), e).into())
 }
 
-#[cfg(target_os = "windows")]
-fn set_windows_wallpaper(path: &Path) -> Result<(), Box<dyn Error>> {
-    let path_as_str = path.to_str().ok_or("Invalid path")?;
-
-    let command_execution_output = Command::new("external_builds\\windows\\WallpaperChanger.exe")
-        .args(Some(path_as_str))
-        .output()
-        .map_err(|e| {
-            format!(
-                "Error: Failed to execute process to change wallpaper: {}",
-                e
-            )
-        })?;
-
-    if !command_execution_output.status.success() {
-        return Err("Error: The command to change the wallpaper failed.".into());
+/// Sets a slice of `path` on every output when it's a panoramic image and the
+/// backend can give each output, picked by name, its own wallpaper. Returns
+/// `false` when the image should be set as usual instead.
+#[cfg(target_os = "linux")]
+fn set_spanned(
+    path: &Path,
+    dark: Option<&Path>,
+    display: &DwDisplayConfig,
+    backend: Option<&DwBackendConfig>,
+) -> Result<bool, Box<dyn Error>> {
+    let registry = build_registry(display, backend);
+    if !registry.select(forced_backend().as_deref())?.names_outputs() {
+        return Ok(false);
     }
 
-    Ok(())
+    let outputs = match list_outputs() {
+        Ok((_, outputs)) => outputs,
+        Err(_) => return Ok(false),
+    };
+    let slices = match span_slices(path, &outputs, display.bezel)? {
+        Some(slices) => slices,
+        None => return Ok(false),
+    };
+    let dark_slices = match dark {
+        Some(dark) => span_slices(dark, &outputs, display.bezel)?,
+        None => None,
+    };
+
+    for (index, (output, slice)) in slices.iter().enumerate() {
+        let display = DwDisplayConfig {
+            output: Some(output.clone()),
+            ..display.clone()
+        };
+        let registry = build_registry(&display, backend);
+        let backend = registry.select(forced_backend().as_deref())?;
+        let dark_slice = match &dark_slices {
+            Some(dark_slices) => Some(dark_slices[index].1.as_path()),
+            None => dark,
+        };
+
+        match dark_slice {
+            Some(dark_slice) => backend.set_themed(slice, dark_slice),
+            None => backend.set(slice),
+        }
+        .map_err(|e| format!("{} backend: {}", backend.name(), e))?;
+    }
+
+    Ok(true)
 }
 
 pub fn read_config_json(path: &str) -> Result<DwConfig, Box<dyn Error>> {
@@ -118,12 +263,34 @@ pub fn read_config_json(path: &str) -> Result<DwConfig, Box<dyn Error>> {
         .map_err(|e| format!("Error: Failed to read file {}: {}", path, e))?;
     let config: DwConfig = serde_json::from_str(&contents)
         .map_err(|e| format!("Error: Failed to parse JSON in file {}: {}", path, e))?;
+
+    if config
+        .backend
+        .as_ref()
+        .is_some_and(|backend| backend.command.is_empty())
+    {
+        return Err(format!("Error: The backend command in {} is empty.", path).into());
+    }
+
     Ok(config)
 }
 
 pub fn write_config_json(config: DwConfig, path: String) -> Result<(), Box<dyn Error>> {
     let json_data = serde_json::to_string_pretty(&config)
         .map_err(|e| format!("Error: Failed to serialize config to JSON: {}", e))?;
+
+    if is_dry_run() {
+        // Compare against the current config in the same layout, so only real
+        // changes show up even when the file on disk isn't pretty-printed.
+        let previous = read_config_json(&path)
+            .ok()
+            .and_then(|previous| serde_json::to_string_pretty(&previous).ok())
+            .unwrap_or_default();
+        println!("[dry-run] would write {}:", path);
+        print!("{}", diff_lines(&previous, &json_data));
+        return Ok(());
+    }
+
     let mut file =
         File::create(&path).map_err(|e| format!("Error: Failed to create file {}: {}", path, e))?;
     file.write_all(json_data.as_bytes())
@@ -131,6 +298,42 @@ pub fn write_config_json(config: DwConfig, path: String) -> Result<(), Box<dyn E
     Ok(())
 }
 
+/// Line diff of `old` and `new`, with "-", "+" or " " in front of every line.
+fn diff_lines(old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // Longest common subsequence table, filled from the end of both texts.
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            diff.push_str(&format!("  {}\n", old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            diff.push_str(&format!("- {}\n", old[i]));
+            i += 1;
+        } else {
+            diff.push_str(&format!("+ {}\n", new[j]));
+            j += 1;
+        }
+    }
+
+    diff
+}
+
 pub fn init() -> Result<(), Box<dyn std::error::Error>> {
     const PATH: &str = "./config/config.json";
 
@@ -168,11 +371,26 @@ pub fn init() -> Result<(), Box<dyn std::error::Error>> {
             child: false,
             sub_index: 0,
         },
+        output_wallpapers: BTreeMap::new(),
+        workspaces: BTreeMap::new(),
+        workspace_wallpapers: BTreeMap::new(),
         time_config: DwTimeConfig {
             preset: DwPreset::DAY,
             interval: 1,
+            at: None,
+            times: Vec::new(),
+            weekdays: Vec::new(),
+            catch_up: false,
+            cron: None,
+            on_calendar: None,
         },
+        scheduler: None,
+        enabled: false,
         candidates: Vec::new(),
+        dark_variants: BTreeMap::new(),
+        display: DwDisplayConfig::default(),
+        backend: None,
+        targets: Vec::new(),
     };
     write_config_json(empty_config, PATH.to_string())?;
 
@@ -192,8 +410,8 @@ pub fn change_config_file(new_config_path: &Path) -> Result<(), Box<dyn Error>>
 
     #[cfg(target_os = "linux")]
     {
-        Command::new("sh")
-            .args(["-c", &format!("mkdir -p {}", CONFIG_DIR_PATH)])
+        Command::new("mkdir")
+            .args(["-p", CONFIG_DIR_PATH])
             .output()
             .map_err(|e| {
                 format!(
@@ -202,8 +420,9 @@ pub fn change_config_file(new_config_path: &Path) -> Result<(), Box<dyn Error>>
                 )
             })?;
 
-        Command::new("sh")
-            .args(["-c", &format!("cp {:?} {}", new_config_path, CONFIG_DIR_PATH)])
+        Command::new("cp")
+            .arg(new_config_path)
+            .arg(CONFIG_DIR_PATH)
             .output()
             .map_err(|e| {
                 format!(
@@ -253,6 +472,7 @@ pub fn found_wpp_path_by_index_in_directory(dir_path: &Path, index: usize) -> Re
         .map(|entry| entry.path())
         .filter(|path| tree_magic::from_filepath(path).starts_with("image/"))
         .collect();
+    skip_dark_variants(&mut paths);
 
     // Sort the paths
     paths.sort();
@@ -278,6 +498,7 @@ pub fn found_wpp_index_by_path_in_directory(dir_path: &Path, target_file_name: &
         .map(|entry| entry.path())
         .filter(|path| tree_magic::from_filepath(path).starts_with("image/"))
         .collect();
+    skip_dark_variants(&mut paths);
 
     // Sort the paths
     paths.sort();
@@ -309,52 +530,27 @@ pub fn list_images_in_directory(directory: &Path) -> Result<Vec<String>, Box<dyn
             }
         }
     }
+    skip_dark_variants(&mut image_paths);
     Ok(image_paths)
 }
-pub fn generate_schedule(preset: DwPreset, interval: u8, task_name: &str, action: &str) -> (String, Vec<String>) {
-    #[cfg(target_os = "linux")]
-    {
-        let cron_string = generate_cron_string(preset, interval, action);
-        (cron_string, Vec::new()) // No Linux, retorna a string de cron e um vetor vazio.
-    }
-
-    #[cfg(target_os = "windows")]
-    {
-        generate_schtasks_command(preset, interval, task_name, action)
-    }
-}
-
-#[cfg(target_os = "linux")]
-fn generate_cron_string(preset: DwPreset, interval: u8, action: &str) -> String {
-    match preset {
-        DwPreset::HOUR => format!("0 */{} * * * {}", interval, action),
-        DwPreset::MINUTE => format!("*/{} * * * * {}", interval, action),
-        DwPreset::DAY => format!("0 0 */{} * * {}", interval, action),
-    }
-}
-
+/// The `schtasks` program and arguments creating the task `task_name`,
+/// running `action` on `schedule`.
 #[cfg(target_os = "windows")]
-fn generate_schtasks_command(preset: DwPreset, interval: u8, task_name: &str, action: &str) -> (String, Vec<String>) {
-    let sc_type = match preset {
-        DwPreset::HOUR => "HOURLY",
-        DwPreset::MINUTE => "MINUTE",
-        DwPreset::DAY => "DAILY",
-    };
-
+pub fn generate_schedule(
+    schedule: &Schedule,
+    task_name: &str,
+    action: &str,
+) -> Result<(String, Vec<String>), Box<dyn Error>> {
     let command = "schtasks".to_string();
-    let args = vec![
+    let mut args = vec![
         "/create".to_string(),
         "/tn".to_string(),
         task_name.to_string(),
         "/tr".to_string(),
         action.to_string(),
-        "/sc".to_string(),
-        sc_type.to_string(),
-        "/mo".to_string(),
-        interval.to_string(),
-        "/IT".to_string(),
     ];
+    args.extend(schedule.schtasks_args()?);
+    args.push("/IT".to_string());
 
-    (command, args)
+    Ok((command, args))
 }
-
@@ -16,6 +16,7 @@
 
 use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Debug)]
 pub struct DwOperationExecutionResult {
@@ -24,7 +25,7 @@ pub struct DwOperationExecutionResult {
     pub message: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 
 pub struct DwWallpaperCandidate {
     pub index: usize,
@@ -35,6 +36,7 @@ pub struct DwWallpaperCandidate {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum DwPreset {
     HOUR,
     MINUTE,
@@ -45,10 +47,189 @@ pub enum DwPreset {
 pub struct DwTimeConfig {
     pub preset: DwPreset,
     pub interval: u8,
+    /// Time of day ("HH:MM") of the DAY preset's change, midnight when unset.
+    #[serde(default)]
+    pub at: Option<String>,
+    /// Times of day to change at ("07:00"), used instead of the preset.
+    #[serde(default)]
+    pub times: Vec<String>,
+    /// Days of the week ("mon", "friday") the DAY preset and `times` apply
+    /// to, every day when empty.
+    #[serde(default)]
+    pub weekdays: Vec<String>,
+    /// Whether `tick` makes every change missed while the computer was off or
+    /// asleep, instead of a single one.
+    #[serde(default)]
+    pub catch_up: bool,
+    /// Cron expression used as is by the cron scheduler.
+    #[serde(default)]
+    pub cron: Option<String>,
+    /// `OnCalendar=` expression used as is by the systemd scheduler.
+    #[serde(default)]
+    pub on_calendar: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DwFillMode {
+    #[default]
+    Fill,
+    Fit,
+    Stretch,
+    Center,
+    Tile,
+}
+
+impl DwFillMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DwFillMode::Fill => "fill",
+            DwFillMode::Fit => "fit",
+            DwFillMode::Stretch => "stretch",
+            DwFillMode::Center => "center",
+            DwFillMode::Tile => "tile",
+        }
+    }
+}
+
+/// How `next` and `previous` move the cursors of the outputs when no
+/// `--output` is given.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DwCycleMode {
+    /// Every output moves one step.
+    #[default]
+    Together,
+    /// Only the output showing its wallpaper for the longest time moves.
+    Independent,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DwDisplayConfig {
+    #[serde(default)]
+    pub mode: DwFillMode,
+    #[serde(default)]
+    pub output: Option<String>,
+    #[serde(default)]
+    pub workspace: Option<u32>,
+    /// Outputs cycling through the candidates with their own cursor.
+    #[serde(default)]
+    pub outputs: Vec<String>,
+    #[serde(default)]
+    pub cycle: DwCycleMode,
+    /// Cut panoramic images across the outputs instead of repeating them.
+    #[serde(default)]
+    pub span: bool,
+    /// Pixels hidden by the monitor frames between two outputs when spanning.
+    #[serde(default)]
+    pub bezel: u32,
+}
+
+/// A connected output, as reported by the compositor or X server.
+///
+/// `width` and `height` are the pixels it shows once rotated, `x` and `y` its
+/// position in the layout and `rotation` is clockwise, in degrees.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DwOutput {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub x: i32,
+    pub y: i32,
+    pub scale: f64,
+    pub rotation: u16,
+}
+
+/// Places other than the desktop that can show the wallpapers.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DwTargetKind {
+    Lockscreen,
+    Greeter,
 }
+
+impl DwTargetKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DwTargetKind::Lockscreen => "lockscreen",
+            DwTargetKind::Greeter => "greeter",
+        }
+    }
+}
+
+/// A lock screen or greeter that gets a wallpaper along with the desktop.
+///
+/// With no `candidates` it shows the desktop wallpaper, otherwise it runs its
+/// own cycle through them, moved by `next`, `previous` and `reset`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DwTargetConfig {
+    pub target: DwTargetKind,
+    #[serde(default)]
+    pub candidates: Vec<String>,
+    #[serde(default)]
+    pub actual_wallpaper: Option<DwWallpaperCandidate>,
+    /// File the greeter reads its background from (greeter only).
+    #[serde(default)]
+    pub file: Option<String>,
+}
+
+/// Programs able to run `dw next` on a schedule.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DwSchedulerKind {
+    Cron,
+    Systemd,
+    Daemon,
+}
+
+impl DwSchedulerKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DwSchedulerKind::Cron => "cron",
+            DwSchedulerKind::Systemd => "systemd",
+            DwSchedulerKind::Daemon => "daemon",
+        }
+    }
+}
+
+/// A user supplied setter, used instead of the built-in backends.
+///
+/// `command` is an argv template; each element may contain the placeholders
+/// `{path}`, `{uri}`, `{output}` and `{mode}`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DwBackendConfig {
+    pub command: Vec<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct DwConfig {
     pub actual_wallpaper: DwWallpaperCandidate,
+    /// Cursor of each output cycled on its own, see `DwDisplayConfig.outputs`.
+    /// Outputs without one start from `actual_wallpaper`.
+    #[serde(default)]
+    pub output_wallpapers: BTreeMap<String, DwWallpaperCandidate>,
+    /// sway/i3 workspaces cycling through their own candidates instead.
+    #[serde(default)]
+    pub workspaces: BTreeMap<String, Vec<String>>,
+    /// Cursor of each workspace in `workspaces`.
+    #[serde(default)]
+    pub workspace_wallpapers: BTreeMap<String, DwWallpaperCandidate>,
     pub time_config: DwTimeConfig,
+    /// Scheduler used by `on`, the first one available when unset.
+    #[serde(default)]
+    pub scheduler: Option<DwSchedulerKind>,
+    /// Whether `dw daemon` changes wallpapers, set by `on` and `off`.
+    #[serde(default)]
+    pub enabled: bool,
     pub candidates: Vec<String>,
+    /// Image shown instead of each of these while the desktop is in dark mode.
+    /// Images without one use a `-dark` sibling, see `dark_variant`.
+    #[serde(default)]
+    pub dark_variants: BTreeMap<String, String>,
+    #[serde(default)]
+    pub display: DwDisplayConfig,
+    #[serde(default)]
+    pub backend: Option<DwBackendConfig>,
+    #[serde(default)]
+    pub targets: Vec<DwTargetConfig>,
 }
@@ -0,0 +1,141 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// src/daemon.rs
+
+use crate::backends::run;
+use chrono::{DateTime, Local};
+use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
+use std::{
+    env,
+    error::Error,
+    fs,
+    path::PathBuf,
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+/// How often the loop looks at the clock and the signal flags.
+const POLL: Duration = Duration::from_millis(250);
+
+/// Longest wait between two checks, so config changes are picked up and a
+/// change that came due during a suspend isn't late for long.
+pub const RECHECK: chrono::Duration = chrono::Duration::minutes(1);
+
+/// `$XDG_RUNTIME_DIR/dwr-daemon.pid`, or `state/daemon.pid` in our directory
+/// when there is no runtime dir.
+fn pidfile_path() -> Result<PathBuf, Box<dyn Error>> {
+    match env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()) {
+        Some(dir) => Ok(PathBuf::from(dir).join("dwr-daemon.pid")),
+        None => Ok(env::current_dir()?.join("state/daemon.pid")),
+    }
+}
+
+/// `true` if `pid` is a running copy of this program and not us.
+///
+/// Comparing names keeps a pid reused by another program from passing.
+fn is_daemon(pid: u32) -> bool {
+    let own = fs::read_to_string("/proc/self/comm");
+    let other = fs::read_to_string(format!("/proc/{}/comm", pid));
+
+    pid != process::id() && matches!((own, other), (Ok(own), Ok(other)) if own == other)
+}
+
+/// Pid of the running daemon, read from its pidfile.
+pub fn running() -> Option<u32> {
+    let pid = fs::read_to_string(pidfile_path().ok()?).ok()?;
+    let pid: u32 = pid.trim().parse().ok()?;
+
+    Some(pid).filter(|pid| is_daemon(*pid))
+}
+
+/// Asks the running daemon, if any, to look at the config right away.
+pub fn wake() -> Result<(), Box<dyn Error>> {
+    match running() {
+        Some(pid) => run("kill", &["-HUP", &pid.to_string()]),
+        None => Ok(()),
+    }
+}
+
+/// Our pidfile, removed when dropped.
+struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    fn create() -> Result<PidFile, Box<dyn Error>> {
+        if let Some(pid) = running() {
+            return Err(format!("Error: The daemon is already running (pid {}).", pid).into());
+        }
+
+        let path = pidfile_path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Error: Failed to create {}: {}", dir.display(), e))?;
+        }
+        fs::write(&path, format!("{}\n", process::id()))
+            .map_err(|e| format!("Error: Failed to write {}: {}", path.display(), e))?;
+
+        Ok(PidFile { path })
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        // A daemon started after us may have taken the file over.
+        let ours =
+            fs::read_to_string(&self.path).is_ok_and(|pid| pid.trim() == process::id().to_string());
+
+        if ours {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Stays resident calling `check`, which does what is due and returns when it
+/// wants to be called again, until SIGTERM or SIGINT. SIGHUP calls it at once.
+///
+/// The wall clock is used rather than a monotonic one, which stops during a
+/// suspend.
+pub fn run_daemon<F>(mut check: F) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut() -> DateTime<Local>,
+{
+    let terminate = Arc::new(AtomicBool::new(false));
+    let wake = Arc::new(AtomicBool::new(false));
+    for signal in [SIGTERM, SIGINT] {
+        signal_hook::flag::register(signal, Arc::clone(&terminate))?;
+    }
+    signal_hook::flag::register(SIGHUP, Arc::clone(&wake))?;
+
+    let _pidfile = PidFile::create()?;
+    let mut next_check = Local::now();
+
+    while !terminate.load(Ordering::Relaxed) {
+        let now = Local::now();
+
+        if wake.swap(false, Ordering::Relaxed) || now >= next_check {
+            next_check = check().min(Local::now() + RECHECK);
+        }
+
+        thread::sleep(POLL);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,252 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// src/displays.rs
+
+use crate::core_models::DwOutput;
+use serde_json::Value;
+use std::{env, error::Error, process::Command};
+
+type OutputParser = fn(&str) -> Result<Vec<DwOutput>, Box<dyn Error>>;
+
+/// Tools able to list the outputs, by preference, with the variable telling
+/// that the session they query is running.
+const SOURCES: [(&str, &[&str], &str, OutputParser); 4] = [
+    (
+        "swaymsg",
+        &["-t", "get_outputs", "--raw"],
+        "SWAYSOCK",
+        parse_swaymsg,
+    ),
+    (
+        "hyprctl",
+        &["monitors", "-j"],
+        "HYPRLAND_INSTANCE_SIGNATURE",
+        parse_hyprctl,
+    ),
+    ("wlr-randr", &["--json"], "WAYLAND_DISPLAY", parse_wlr_randr),
+    ("xrandr", &["--query"], "DISPLAY", parse_xrandr),
+];
+
+/// The connected and enabled outputs of the current session, and the tool that
+/// reported them.
+pub fn list_outputs() -> Result<(&'static str, Vec<DwOutput>), Box<dyn Error>> {
+    let mut errors = Vec::new();
+
+    for (program, args, variable, parser) in SOURCES {
+        if env::var_os(variable).is_none() {
+            continue;
+        }
+
+        let output = match Command::new(program).args(args).output() {
+            Ok(output) if output.status.success() => output,
+            Ok(output) => {
+                errors.push(format!(
+                    "{} failed: {}",
+                    program,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ));
+                continue;
+            }
+            Err(e) => {
+                errors.push(format!("{}: {}", program, e));
+                continue;
+            }
+        };
+
+        match parser(&String::from_utf8_lossy(&output.stdout)) {
+            Ok(outputs) => return Ok((program, outputs)),
+            Err(e) => errors.push(format!("{}: {}", program, e)),
+        }
+    }
+
+    if errors.is_empty() {
+        return Err("Error: No graphical session found to list the outputs of.".into());
+    }
+
+    Err(format!("Error: Failed to list the outputs ({}).", errors.join("; ")).into())
+}
+
+/// Degrees of a wayland transform name ("normal", "90", "flipped-270"...).
+fn transform_rotation(transform: &str) -> u16 {
+    transform
+        .trim_start_matches("flipped")
+        .trim_start_matches('-')
+        .parse()
+        .unwrap_or(0)
+}
+
+/// Output size once rotated, from the size of its mode.
+fn rotated(width: u32, height: u32, rotation: u16) -> (u32, u32) {
+    if rotation % 180 == 90 {
+        (height, width)
+    } else {
+        (width, height)
+    }
+}
+
+fn number(value: &Value) -> i64 {
+    value.as_i64().unwrap_or(0)
+}
+
+/// Parses `swaymsg -t get_outputs --raw`.
+pub fn parse_swaymsg(reply: &str) -> Result<Vec<DwOutput>, Box<dyn Error>> {
+    let outputs: Vec<Value> = serde_json::from_str(reply)?;
+
+    Ok(outputs
+        .iter()
+        .filter(|output| output["active"].as_bool().unwrap_or(false))
+        .map(|output| {
+            let rotation = transform_rotation(output["transform"].as_str().unwrap_or("normal"));
+            let (width, height) = rotated(
+                number(&output["current_mode"]["width"]) as u32,
+                number(&output["current_mode"]["height"]) as u32,
+                rotation,
+            );
+
+            DwOutput {
+                name: output["name"].as_str().unwrap_or_default().to_string(),
+                width,
+                height,
+                x: number(&output["rect"]["x"]) as i32,
+                y: number(&output["rect"]["y"]) as i32,
+                scale: output["scale"].as_f64().unwrap_or(1.0),
+                rotation,
+            }
+        })
+        .collect())
+}
+
+/// Parses `hyprctl monitors -j`.
+pub fn parse_hyprctl(reply: &str) -> Result<Vec<DwOutput>, Box<dyn Error>> {
+    let monitors: Vec<Value> = serde_json::from_str(reply)?;
+
+    Ok(monitors
+        .iter()
+        .filter(|monitor| !monitor["disabled"].as_bool().unwrap_or(false))
+        .map(|monitor| {
+            // wl_output transforms: 0-3 rotate by 90 degrees, 4-7 also flip.
+            let rotation = (number(&monitor["transform"]) % 4 * 90) as u16;
+            let (width, height) = rotated(
+                number(&monitor["width"]) as u32,
+                number(&monitor["height"]) as u32,
+                rotation,
+            );
+
+            DwOutput {
+                name: monitor["name"].as_str().unwrap_or_default().to_string(),
+                width,
+                height,
+                x: number(&monitor["x"]) as i32,
+                y: number(&monitor["y"]) as i32,
+                scale: monitor["scale"].as_f64().unwrap_or(1.0),
+                rotation,
+            }
+        })
+        .collect())
+}
+
+/// Parses `wlr-randr --json`.
+pub fn parse_wlr_randr(reply: &str) -> Result<Vec<DwOutput>, Box<dyn Error>> {
+    let heads: Vec<Value> = serde_json::from_str(reply)?;
+
+    Ok(heads
+        .iter()
+        .filter(|head| head["enabled"].as_bool().unwrap_or(false))
+        .filter_map(|head| {
+            let mode = head["modes"]
+                .as_array()?
+                .iter()
+                .find(|mode| mode["current"].as_bool().unwrap_or(false))?;
+            let rotation = transform_rotation(head["transform"].as_str().unwrap_or("normal"));
+            let (width, height) = rotated(
+                number(&mode["width"]) as u32,
+                number(&mode["height"]) as u32,
+                rotation,
+            );
+
+            Some(DwOutput {
+                name: head["name"].as_str().unwrap_or_default().to_string(),
+                width,
+                height,
+                x: number(&head["position"]["x"]) as i32,
+                y: number(&head["position"]["y"]) as i32,
+                scale: head["scale"].as_f64().unwrap_or(1.0),
+                rotation,
+            })
+        })
+        .collect())
+}
+
+/// Parses `xrandr --query`.
+///
+/// Only the output lines matter, e.g.
+/// `HDMI-1 connected primary 1080x1920+2560+0 left (normal left ...) 527mm x 296mm`.
+/// Their geometry is already rotated, and X has no per-output scale.
+pub fn parse_xrandr(reply: &str) -> Result<Vec<DwOutput>, Box<dyn Error>> {
+    let mut outputs = Vec::new();
+
+    for line in reply
+        .lines()
+        .filter(|line| !line.starts_with(char::is_whitespace))
+    {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.get(1) != Some(&"connected") {
+            continue;
+        }
+
+        // Connected outputs that are turned off have no geometry.
+        let geometry = match words.iter().position(|word| parse_geometry(word).is_some()) {
+            Some(position) => position,
+            None => continue,
+        };
+        let (width, height, x, y) = parse_geometry(words[geometry]).unwrap();
+
+        let rotation = match words.get(geometry + 1) {
+            Some(&"left") => 90,
+            Some(&"inverted") => 180,
+            Some(&"right") => 270,
+            _ => 0,
+        };
+
+        outputs.push(DwOutput {
+            name: words[0].to_string(),
+            width,
+            height,
+            x,
+            y,
+            scale: 1.0,
+            rotation,
+        });
+    }
+
+    Ok(outputs)
+}
+
+/// Splits an X geometry (`1920x1080+0+0`, offsets may be negative) into
+/// width, height, x and y.
+fn parse_geometry(geometry: &str) -> Option<(u32, u32, i32, i32)> {
+    let (width, rest) = geometry.split_once('x')?;
+    let offsets_at = rest.find(['+', '-'])?;
+    let (height, offsets) = rest.split_at(offsets_at);
+    let y_at = offsets[1..].find(['+', '-'])? + 1;
+    let (x, y) = offsets.split_at(y_at);
+
+    Some((
+        width.parse().ok()?,
+        height.parse().ok()?,
+        x.parse().ok()?,
+        y.parse().ok()?,
+    ))
+}
@@ -14,14 +14,29 @@
 
 // src/main.rs
 
+mod backends;
 mod clap_models;
+#[cfg(target_os = "linux")]
+mod color_scheme;
 mod core_functions;
 mod core_models;
+#[cfg(target_os = "linux")]
+mod daemon;
+mod displays;
 mod operations;
+mod schedule;
+#[cfg(target_os = "linux")]
+mod schedulers;
+#[cfg(target_os = "linux")]
+mod session;
+#[cfg(target_os = "linux")]
+mod span;
+mod workspaces;
 use crate::clap_models::{Cli, Commands};
+use crate::core_functions::{set_dry_run, set_forced_backend};
 use crate::operations::{
-    add_wallpaper, next, off, on, perform_init, previous, reset, rm_wallpaper, set_config,
-    set_preset, set_wallpaper, show_config,
+    add_wallpaper, backends, daemon, next, off, on, outputs, perform_init, previous, reset, rm_wallpaper, set_config,
+    set_preset, set_schedule, set_wallpaper, show_config, tick, watch_color_scheme, watch_workspaces,
 };
 use clap::Parser;
 use std::{process::ExitCode, path::PathBuf, env};
@@ -64,63 +79,50 @@ fn main() -> ExitCode {
     }
 
     let cli: Cli = Cli::parse();
-    let operation_res;
-
-    match &cli.command {
-        Commands::AddWallpaper { path } => {
-            operation_res = add_wallpaper(path);
-        }
-
-        Commands::RmWallpaper { path } => {
-            operation_res = rm_wallpaper(path);
-        }
-
-        Commands::Preset { preset, interval } => {
-            operation_res = set_preset(&preset, *interval);
-        }
-
-        Commands::Next => {
-            operation_res = next();
-        }
-
-        Commands::Previous => {
-            operation_res = previous();
-        }
-
-        Commands::Reset => {
-            operation_res = reset();
-        }
-
-        Commands::SetWallpaper { path } => {
-            operation_res = set_wallpaper(path);
-        }
-
-        Commands::Off => {
-            operation_res = off();
-        }
-
-        Commands::On => {
-            operation_res = on();
-        }
-
-        Commands::ShowConfig => {
-            operation_res = show_config();
-        }
-
-        Commands::SetConfig { path } => {
-            operation_res = set_config(path);
-        }
-
-        Commands::Init => {
-            operation_res = perform_init();
+    set_dry_run(cli.dry_run);
+    set_forced_backend(cli.backend.clone());
+
+    let operation_res = match &cli.command {
+        Commands::AddWallpaper { path } => add_wallpaper(path),
+        Commands::RmWallpaper { path } => rm_wallpaper(path),
+        Commands::Preset {
+            preset,
+            interval,
+            at,
+        } => set_preset(preset, *interval, at.as_ref()),
+        Commands::Schedule {
+            times,
+            weekdays,
+            cron,
+            on_calendar,
+        } => set_schedule(times, weekdays, cron.as_ref(), on_calendar.as_ref()),
+        Commands::Next { output, workspace } => next(output.as_ref(), workspace.as_ref()),
+        Commands::Tick { catch_up } => tick(*catch_up),
+        Commands::Previous { output, workspace } => previous(output.as_ref(), workspace.as_ref()),
+        Commands::Reset => reset(),
+        Commands::SetWallpaper { path, dark, output } => {
+            set_wallpaper(path, dark.as_ref(), output.as_ref())
         }
+        Commands::Off => off(),
+        Commands::On => on(),
+        Commands::ShowConfig => show_config(),
+        Commands::SetConfig { path } => set_config(path),
+        Commands::Init => perform_init(),
+        Commands::Backends => backends(),
+        Commands::Outputs => outputs(),
+        Commands::WatchWorkspaces => watch_workspaces(),
+        Commands::WatchColorScheme => watch_color_scheme(),
+        Commands::Daemon => daemon(),
     };
 
+    let message = operation_res.message.unwrap_or_default();
     if operation_res.success {
-        println!("{:?}", operation_res);
-        return ExitCode::SUCCESS;
+        if !message.is_empty() {
+            println!("{}", message);
+        }
+        ExitCode::SUCCESS
     } else {
-        eprintln!("{:?}", operation_res);
-        return ExitCode::FAILURE;
+        eprintln!("{} (exit code {})", message, operation_res.exit_code);
+        ExitCode::FAILURE
     }
 }
@@ -14,22 +14,87 @@
 
 // src/operations.rs
 
-use chrono::Local;
+use chrono::{DateTime, Local};
 use crate::core_functions::{
-    change_config_file, change_wallpaper, found_wpp_index_by_path_in_directory,
+    build_registry, change_config_file, change_target_wallpaper, change_wallpaper, dark_variant, forced_backend, found_wpp_index_by_path_in_directory,
     found_wpp_path_by_index_in_directory, init, list_images_in_directory, read_config_json,
-    write_config_json, generate_schedule
+    write_config_json
 };
-use crate::core_models::{DwOperationExecutionResult, DwPreset};
+use crate::core_models::{
+    DwConfig, DwCycleMode, DwOperationExecutionResult, DwPreset, DwTargetConfig,
+    DwWallpaperCandidate,
+};
+use crate::displays::list_outputs;
+use crate::schedule::Schedule;
+use crate::workspaces::{self, focused_workspace, Workspace};
+use std::{
+    collections::HashMap,
+    path::Path
+};
+
+#[cfg(target_os = "windows")]
+use crate::core_functions::generate_schedule;
+#[cfg(target_os = "windows")]
+use std::{env, process::Command};
+
+#[cfg(target_os = "linux")]
+use crate::{color_scheme, schedulers, session::import_session_environment};
+#[cfg(target_os = "linux")]
 use std::{
-    path::Path,
-    usize,
-    env,
-    process::Command
+    sync::{Arc, Mutex, PoisonError},
+    thread,
 };
 
-pub fn set_wallpaper(path: &String) -> DwOperationExecutionResult {
-    match change_wallpaper(Path::new(path)) {
+pub fn set_wallpaper(
+    path: &String,
+    dark: Option<&String>,
+    output: Option<&String>,
+) -> DwOperationExecutionResult {
+    let res = apply_wallpaper(path, dark, output);
+
+    //Lock screen e greeter só acompanham o desktop inteiro
+    let has_targets = read_config_json("config/config.json")
+        .map(|config| !config.targets.is_empty())
+        .unwrap_or(false);
+    if !res.success || output.is_some() || !has_targets {
+        return res;
+    }
+
+    update_targets(path, None)
+}
+
+/// Shows `path` (or `dark`) on the desktop, on every output or only `output`.
+fn apply_wallpaper(
+    path: &String,
+    dark: Option<&String>,
+    output: Option<&String>,
+) -> DwOperationExecutionResult {
+    //Sem config usa os padrões, mas uma config inválida não é ignorada
+    let (mut display, backend, dark_variants) = match read_config_json("config/config.json") {
+        Ok(config) => (config.display, config.backend, config.dark_variants),
+        Err(_) if !Path::new("config/config.json").exists() => Default::default(),
+        Err(e) => {
+            return DwOperationExecutionResult {
+                success: false,
+                exit_code: 1,
+                message: Some(e.to_string()),
+            };
+        }
+    };
+
+    if let Some(output) = output {
+        display.output = Some(output.clone());
+    }
+
+    //Sem --dark, usa a variante escura da config ou a do nome do arquivo (foo-dark.jpg)
+    let dark = dark.cloned().or_else(|| dark_variant(path, &dark_variants));
+
+    match change_wallpaper(
+        Path::new(path),
+        dark.as_deref().map(Path::new),
+        &display,
+        backend.as_ref(),
+    ) {
         Ok(_) => DwOperationExecutionResult {
             success: true,
             exit_code: 0,
@@ -43,21 +108,256 @@ pub fn set_wallpaper(path: &String) -> DwOperationExecutionResult {
     }
 }
 
+pub fn backends() -> DwOperationExecutionResult {
+    let (display, backend) = read_config_json("config/config.json")
+        .map(|config| (config.display, config.backend))
+        .unwrap_or_default();
+
+    #[cfg(target_os = "linux")]
+    import_session_environment();
+
+    let registry = build_registry(&display, backend.as_ref());
+    let forced = forced_backend();
+
+    print!("{}", registry.report(forced.as_deref()));
+
+    match registry.select(forced.as_deref()) {
+        Ok(_) => DwOperationExecutionResult {
+            success: true,
+            exit_code: 0,
+            message: None,
+        },
+        Err(err) => DwOperationExecutionResult {
+            success: false,
+            exit_code: 40,
+            message: Some(err.to_string()),
+        },
+    }
+}
+
+pub fn outputs() -> DwOperationExecutionResult {
+    #[cfg(target_os = "linux")]
+    import_session_environment();
+
+    match list_outputs() {
+        Ok((source, outputs)) => {
+            println!("Outputs reported by {}:", source);
+            for output in outputs {
+                println!(
+                    "  {}: {}x{} at {},{} scale {} rotation {}",
+                    output.name,
+                    output.width,
+                    output.height,
+                    output.x,
+                    output.y,
+                    output.scale,
+                    output.rotation
+                );
+            }
+
+            DwOperationExecutionResult {
+                success: true,
+                exit_code: 0,
+                message: None,
+            }
+        }
+        Err(e) => DwOperationExecutionResult {
+            success: false,
+            exit_code: 41,
+            message: Some(e.to_string()),
+        },
+    }
+}
+
+pub fn watch_workspaces() -> DwOperationExecutionResult {
+    #[cfg(target_os = "linux")]
+    import_session_environment();
+
+    //Último wallpaper aplicado em cada output, para não repetir o mesmo
+    let mut shown: HashMap<String, String> = HashMap::new();
+
+    let res = workspaces::watch_workspaces(|focused| {
+        let config = read_config_json("config/config.json")?;
+
+        let path = match config.workspaces.get(&focused.name) {
+            Some(candidates) if !candidates.is_empty() => {
+                match config.workspace_wallpapers.get(&focused.name) {
+                    Some(actual) => actual.path.clone(),
+                    None => first_candidate(candidates)
+                        .map_err(|res| res.message.unwrap_or_default())?
+                        .path,
+                }
+            }
+            _ => config
+                .output_wallpapers
+                .get(&focused.output)
+                .unwrap_or(&config.actual_wallpaper)
+                .path
+                .clone(),
+        };
+
+        if path.is_empty() || shown.get(&focused.output) == Some(&path) {
+            return Ok(());
+        }
+
+        let output = Some(&focused.output).filter(|_| config_supports_outputs(&config));
+        let res = apply_wallpaper(&path, None, output);
+        if res.success {
+            shown.insert(focused.output.clone(), path);
+        } else {
+            //Um wallpaper com problema não deve derrubar o watcher
+            eprintln!("{:?}", res);
+        }
+
+        Ok(())
+    });
+
+    match res {
+        Ok(_) => DwOperationExecutionResult {
+            success: true,
+            exit_code: 0,
+            message: None,
+        },
+        Err(e) => DwOperationExecutionResult {
+            success: false,
+            exit_code: 42,
+            message: Some(e.to_string()),
+        },
+    }
+}
+
+pub fn watch_color_scheme() -> DwOperationExecutionResult {
+    #[cfg(target_os = "linux")]
+    let res = {
+        import_session_environment();
+
+        color_scheme::watch_color_scheme(|| {
+            let res = reapply_wallpapers();
+            //Um wallpaper com problema não deve derrubar o watcher
+            if !res.success {
+                eprintln!("{:?}", res);
+            }
+
+            Ok(())
+        })
+    };
+    #[cfg(not(target_os = "linux"))]
+    let res: Result<(), Box<dyn std::error::Error>> =
+        Err("Error: Color scheme events need a Linux session.".into());
+
+    match res {
+        Ok(_) => DwOperationExecutionResult {
+            success: true,
+            exit_code: 0,
+            message: None,
+        },
+        Err(e) => DwOperationExecutionResult {
+            success: false,
+            exit_code: 47,
+            message: Some(e.to_string()),
+        },
+    }
+}
+
+/// Applies again every wallpaper on screen and on the targets, so backends
+/// that only show one of the light and dark variants switch to the other.
+fn reapply_wallpapers() -> DwOperationExecutionResult {
+    let config = match read_config_json("config/config.json") {
+        Ok(config) => config,
+        Err(e) => {
+            return DwOperationExecutionResult {
+                success: false,
+                exit_code: 48,
+                message: Some(e.to_string()),
+            };
+        }
+    };
+
+    let supports_outputs = config_supports_outputs(&config);
+
+    //Mesmos outputs que o step_cycle aplica
+    let mut wallpapers: Vec<(Option<String>, String)> = cycle_cursors(&config)
+        .into_iter()
+        .map(|cursor| {
+            let path = cursor_wallpaper(&config, cursor.as_ref()).path.clone();
+            (cursor, path)
+        })
+        .collect();
+
+    if !supports_outputs {
+        wallpapers.truncate(1);
+        wallpapers[0].0 = None;
+    }
+
+    //O workspace focado pode ter wallpapers próprios
+    let focused = if config.workspaces.is_empty() {
+        None
+    } else {
+        focused_workspace().ok().flatten()
+    };
+    if let Some(focused) = focused {
+        if let Some(candidates) = config
+            .workspaces
+            .get(&focused.name)
+            .filter(|candidates| !candidates.is_empty())
+        {
+            let path = match config.workspace_wallpapers.get(&focused.name) {
+                Some(actual) => actual.path.clone(),
+                None => match first_candidate(candidates) {
+                    Ok(first) => first.path,
+                    Err(res) => return res,
+                },
+            };
+
+            if supports_outputs {
+                wallpapers.retain(|(output, _)| output.as_ref() != Some(&focused.output));
+                wallpapers.push((Some(focused.output.clone()), path));
+            } else {
+                wallpapers = vec![(None, path)];
+            }
+        }
+    }
+
+    let desktop_path = wallpapers[0].1.clone();
+
+    for (output, path) in wallpapers {
+        if path.is_empty() {
+            continue;
+        }
+
+        let res = apply_wallpaper(&path, None, output.as_ref());
+        if !res.success {
+            return res;
+        }
+    }
+
+    if config.targets.is_empty() || desktop_path.is_empty() {
+        return DwOperationExecutionResult {
+            success: true,
+            exit_code: 0,
+            message: None,
+        };
+    }
+
+    //Os targets com ciclo próprio ficam no wallpaper atual
+    update_targets(&desktop_path, Some(&|actual, _| Ok(actual.clone())))
+}
+
 pub fn show_config() -> DwOperationExecutionResult {
-    match read_config_json(&"./config/config.json".to_string()) {
+    match read_config_json("./config/config.json") {
         Ok(config) => {
-            return DwOperationExecutionResult {
+            DwOperationExecutionResult {
                 success: true,
                 exit_code: 0,
                 message: Some(serde_json::to_string_pretty(&config).unwrap()),
-            };
+            }
         }
         Err(err) => {
-            return DwOperationExecutionResult {
+            DwOperationExecutionResult {
                 success: false,
                 exit_code: 2,
                 message: Some(err.to_string()),
-            };
+            }
         }
     }
 }
@@ -65,18 +365,18 @@ pub fn show_config() -> DwOperationExecutionResult {
 pub fn perform_init() -> DwOperationExecutionResult {
     match init() {
         Ok(_) => {
-            return DwOperationExecutionResult {
+            DwOperationExecutionResult {
                 success: true,
                 exit_code: 0,
                 message: None,
-            };
+            }
         }
         Err(e) => {
-            return DwOperationExecutionResult {
+            DwOperationExecutionResult {
                 success: false,
                 exit_code: 3,
                 message: Some(e.to_string()),
-            };
+            }
         }
     }
 }
@@ -84,19 +384,19 @@ pub fn perform_init() -> DwOperationExecutionResult {
 pub fn set_config(path: &String) -> DwOperationExecutionResult {
     match change_config_file(Path::new(&path)) {
         Ok(_) => {
-            return DwOperationExecutionResult {
+            DwOperationExecutionResult {
                 success: true,
                 exit_code: 0,
                 message: None,
-            };
+            }
         }
 
         Err(e) => {
-            return DwOperationExecutionResult {
+            DwOperationExecutionResult {
                 success: false,
                 exit_code: 4,
                 message: Some(e.to_string()),
-            };
+            }
         }
     }
 }
@@ -122,39 +422,39 @@ pub fn add_wallpaper(path: &String) -> DwOperationExecutionResult {
         };
     }
 
-    match read_config_json(&"config/config.json".to_string()) {
+    match read_config_json("config/config.json") {
         Ok(mut config) => {
             config.candidates.push(path.clone());
 
             match write_config_json(config, "config/config.json".to_string()) {
                 Ok(_) => {
-                    return DwOperationExecutionResult {
+                    DwOperationExecutionResult {
                         success: true,
                         exit_code: 0,
                         message: None,
-                    };
+                    }
                 }
                 Err(e) => {
-                    return DwOperationExecutionResult {
+                    DwOperationExecutionResult {
                         success: false,
                         exit_code: 6,
                         message: Some(e.to_string()),
-                    };
+                    }
                 }
             }
         }
         Err(e) => {
-            return DwOperationExecutionResult {
+            DwOperationExecutionResult {
                 success: false,
                 exit_code: 7,
                 message: Some(e.to_string()),
-            };
+            }
         }
     }
 }
 
 pub fn rm_wallpaper(path: &String) -> DwOperationExecutionResult {
-    match read_config_json(&"config/config.json".to_string()) {
+    match read_config_json("config/config.json") {
         Ok(mut config) => {
             if let Some(index) = config.candidates.iter().position(|x| x == path) {     
                 config.candidates.remove(index);
@@ -176,37 +476,29 @@ pub fn rm_wallpaper(path: &String) -> DwOperationExecutionResult {
                     }
                 }
             }
-            return DwOperationExecutionResult {
+            DwOperationExecutionResult {
                 success: false,
                 exit_code: 8,
                 message: Some("Wallpaper not found in config".to_string()),
-            };
+            }
         }
         Err(e) => {
-            return DwOperationExecutionResult {
+            DwOperationExecutionResult {
                 success: false,
                 exit_code: 9,
                 message: Some(e.to_string()),
-            };
+            }
         }
     }
 }
 
-pub fn set_preset(preset: &str, interval: Option<u8>) -> DwOperationExecutionResult {
-    match read_config_json(&"config/config.json".to_string()) {
+pub fn set_preset(preset: &str, interval: Option<u8>, at: Option<&String>) -> DwOperationExecutionResult {
+    match read_config_json("config/config.json") {
         Ok(mut config) => {
-            let enum_preset;
-
-            match preset {
-                "minute" => {
-                    enum_preset = DwPreset::MINUTE;
-                }
-                "hour" => {
-                    enum_preset = DwPreset::HOUR;
-                }
-                "day" => {
-                    enum_preset = DwPreset::DAY;
-                }
+            let enum_preset = match preset {
+                "minute" => DwPreset::MINUTE,
+                "hour" => DwPreset::HOUR,
+                "day" => DwPreset::DAY,
                 _ => {
                     return DwOperationExecutionResult {
                         success: false,
@@ -218,53 +510,498 @@ pub fn set_preset(preset: &str, interval: Option<u8>) -> DwOperationExecutionRes
 
             config.time_config.preset = enum_preset;
 
-            if interval != None {
-                config.time_config.interval = interval.unwrap();
+            if let Some(interval) = interval {
+                config.time_config.interval = interval;
+            }
+
+            //O preset volta a valer no lugar de horários, dias e expressões
+            config.time_config.at = at.cloned();
+            config.time_config.times.clear();
+            config.time_config.weekdays.clear();
+            config.time_config.cron = None;
+            config.time_config.on_calendar = None;
+
+            if let Err(e) = Schedule::from_config(&config.time_config) {
+                return DwOperationExecutionResult {
+                    success: false,
+                    exit_code: 49,
+                    message: Some(e.to_string()),
+                };
             }
 
             match write_config_json(config, "./config/config.json".to_string()) {
                 Ok(_) => {
-                    return DwOperationExecutionResult {
+                    DwOperationExecutionResult {
                         success: true,
                         exit_code: 0,
                         message: None,
-                    };
+                    }
                 }
                 Err(e) => {
-                    return DwOperationExecutionResult {
+                    DwOperationExecutionResult {
                         success: false,
                         exit_code: 11,
                         message: Some(e.to_string()),
-                    };
+                    }
                 }
             }
         }
         Err(e) => {
-            return DwOperationExecutionResult {
+            DwOperationExecutionResult {
                 success: false,
                 exit_code: 12,
                 message: Some(e.to_string()),
+            }
+        }
+    }
+}
+
+/// Changes the wallpaper at `times`, on `weekdays`, or on a raw `cron` or
+/// `on_calendar` expression instead of the preset.
+pub fn set_schedule(
+    times: &[String],
+    weekdays: &[String],
+    cron: Option<&String>,
+    on_calendar: Option<&String>,
+) -> DwOperationExecutionResult {
+    if times.is_empty() && weekdays.is_empty() && cron.is_none() && on_calendar.is_none() {
+        return DwOperationExecutionResult {
+            success: false,
+            exit_code: 49,
+            message: Some("Give --times, --weekdays, --cron or --on-calendar".to_string()),
+        };
+    }
+
+    let mut config = match read_config_json("config/config.json") {
+        Ok(config) => config,
+        Err(e) => {
+            return DwOperationExecutionResult {
+                success: false,
+                exit_code: 50,
+                message: Some(e.to_string()),
+            };
+        }
+    };
+
+    config.time_config.times = times.to_vec();
+    config.time_config.weekdays = weekdays.to_vec();
+    config.time_config.cron = cron.cloned();
+    config.time_config.on_calendar = on_calendar.cloned();
+
+    if let Err(e) = Schedule::from_config(&config.time_config) {
+        return DwOperationExecutionResult {
+            success: false,
+            exit_code: 49,
+            message: Some(e.to_string()),
+        };
+    }
+
+    match write_config_json(config, "./config/config.json".to_string()) {
+        Ok(_) => DwOperationExecutionResult {
+            success: true,
+            exit_code: 0,
+            message: None,
+        },
+        Err(e) => DwOperationExecutionResult {
+            success: false,
+            exit_code: 51,
+            message: Some(e.to_string()),
+        },
+    }
+}
+
+pub fn previous(output: Option<&String>, workspace: Option<&String>) -> DwOperationExecutionResult {
+    step_cycle(output, workspace, &previous_candidate, 13, 18)
+}
+
+pub fn next(output: Option<&String>, workspace: Option<&String>) -> DwOperationExecutionResult {
+    step_cycle(output, workspace, &next_candidate, 19, 26)
+}
+
+/// Most changes `tick` makes at once, however long the computer was off.
+const MAX_CATCH_UP: usize = 1000;
+
+/// Moves the cycle if a change came due since the last one, so it can run at
+/// login, on resume or as often as wanted without changing too often. With
+/// `catch_up` (or `catch_up` in the config) it moves once for every change
+/// missed meanwhile, otherwise once.
+pub fn tick(catch_up: bool) -> DwOperationExecutionResult {
+    let config = match read_config_json("config/config.json") {
+        Ok(config) => config,
+        Err(e) => {
+            return DwOperationExecutionResult {
+                success: false,
+                exit_code: 54,
+                message: Some(e.to_string()),
+            };
+        }
+    };
+
+    let schedule = match Schedule::from_config(&config.time_config) {
+        Ok(schedule) => schedule,
+        Err(e) => {
+            return DwOperationExecutionResult {
+                success: false,
+                exit_code: 55,
+                message: Some(e.to_string()),
+            };
+        }
+    };
+
+    let last = last_change(&config);
+    let limit = if catch_up || config.time_config.catch_up {
+        MAX_CATCH_UP
+    } else {
+        1
+    };
+    let changes = match schedule.due_changes(last, Local::now(), limit) {
+        Some(changes) => changes,
+        None => {
+            return DwOperationExecutionResult {
+                success: false,
+                exit_code: 55,
+                message: Some(
+                    "Error: `tick` can't follow `cron` and `on_calendar` expressions.".to_string(),
+                ),
+            };
+        }
+    };
+
+    if changes == 0 {
+        return DwOperationExecutionResult {
+            success: true,
+            exit_code: 0,
+            message: schedule
+                .next_after(last)
+                .map(|due| format!("No change due until {}", due.format("%Y-%m-%d %H:%M"))),
+        };
+    }
+
+    //Avança o cursor todas as vezes antes de aplicar só o último wallpaper
+    let step = |actual: &DwWallpaperCandidate, candidates: &[String]| {
+        (0..changes).try_fold(actual.clone(), |stepped, _| next_candidate(&stepped, candidates))
+    };
+
+    step_cycle(None, None, &step, 54, 56)
+}
+
+type CandidateStep<'a> = &'a dyn Fn(
+    &DwWallpaperCandidate,
+    &[String],
+) -> Result<DwWallpaperCandidate, DwOperationExecutionResult>;
+
+/// Moves the cursors picked by `output`, `workspace` and the cycle mode with
+/// `step`, saves them and applies the new wallpapers.
+///
+/// Without `output` and `workspace` the focused sway/i3 workspace is moved if it
+/// has its own candidates.
+fn step_cycle(
+    output: Option<&String>,
+    workspace: Option<&String>,
+    step: CandidateStep,
+    read_exit_code: i32,
+    write_exit_code: i32,
+) -> DwOperationExecutionResult {
+    let mut config = match read_config_json("config/config.json") {
+        Ok(config) => config,
+        Err(e) => {
+            return DwOperationExecutionResult {
+                success: false,
+                exit_code: read_exit_code,
+                message: Some(e.to_string()),
+            };
+        }
+    };
+
+    //Workspaces com wallpapers próprios (sway/i3)
+    let focused = if config.workspaces.is_empty() {
+        None
+    } else {
+        #[cfg(target_os = "linux")]
+        import_session_environment();
+
+        focused_workspace().ok().flatten()
+    };
+    let workspace = match (workspace, output) {
+        (Some(workspace), _) => Some(workspace.clone()),
+        (None, None) => focused
+            .as_ref()
+            .map(|focused| focused.name.clone())
+            .filter(|name| config.workspaces.contains_key(name)),
+        (None, Some(_)) => None,
+    };
+    if let Some(workspace) = workspace {
+        return step_workspace(config, &workspace, focused, step, write_exit_code);
+    }
+
+    let cursors = cycle_cursors(&config);
+
+    let targets: Vec<Option<String>> = match output {
+        Some(output) => vec![Some(output.clone())],
+        None => match config.display.cycle {
+            DwCycleMode::Together => cursors,
+            DwCycleMode::Independent => cursors
+                .into_iter()
+                .min_by_key(|cursor| cursor_wallpaper(&config, cursor.as_ref()).date_set)
+                .into_iter()
+                .collect(),
+        },
+    };
+
+    let mut wallpapers: Vec<(Option<String>, String)> = Vec::new();
+
+    for target in targets {
+        let actual = cursor_wallpaper(&config, target.as_ref());
+
+        let stepped = match step(actual, &config.candidates) {
+            Ok(stepped) => stepped,
+            Err(res) => return res,
+        };
+
+        wallpapers.push((target.clone(), stepped.path.clone()));
+        match target {
+            Some(output) => {
+                config.output_wallpapers.insert(output, stepped);
+            }
+            None => config.actual_wallpaper = stepped,
+        }
+    }
+
+    //O wallpaper global cobre todas as telas, as com cursor próprio voltam por cima
+    if wallpapers.iter().any(|(target, _)| target.is_none()) {
+        for (output, actual) in &config.output_wallpapers {
+            if !wallpapers.iter().any(|(target, _)| target.as_ref() == Some(output)) {
+                wallpapers.push((Some(output.clone()), actual.path.clone()));
+            }
+        }
+    }
+
+    let supports_outputs = config_supports_outputs(&config);
+
+    //Backends sem suporte a outputs mostram uma só imagem em todas as telas
+    if !supports_outputs {
+        wallpapers.truncate(1);
+    }
+
+    let desktop_path = wallpapers.first().map(|(_, path)| path.clone());
+
+    //Os cursores só andam se a troca der certo, senão a próxima tentativa pula um wallpaper
+    for (target, path) in wallpapers {
+        let target = target.filter(|_| supports_outputs);
+        let res = apply_wallpaper(&path, None, target.as_ref());
+
+        if !res.success {
+            return res;
+        }
+    }
+
+    if let Err(e) = write_config_json(config, "./config/config.json".into()) {
+        return DwOperationExecutionResult {
+            success: false,
+            exit_code: write_exit_code,
+            message: Some(e.to_string()),
+        };
+    }
+
+    match desktop_path {
+        Some(desktop_path) => update_targets(&desktop_path, Some(step)),
+        None => DwOperationExecutionResult {
+            success: true,
+            exit_code: 0,
+            message: None,
+        },
+    }
+}
+
+/// Cursors moved by a plain `next`: one per output of `display.outputs` or,
+/// without those, the global one (`None`) followed by the outputs that got a
+/// cursor of their own from `--output`.
+fn cycle_cursors(config: &DwConfig) -> Vec<Option<String>> {
+    if config.display.outputs.is_empty() {
+        std::iter::once(None)
+            .chain(config.output_wallpapers.keys().cloned().map(Some))
+            .collect()
+    } else {
+        config.display.outputs.iter().cloned().map(Some).collect()
+    }
+}
+
+/// Wallpaper of `output`'s cursor, the global one for `None` and for outputs
+/// without a cursor yet.
+fn cursor_wallpaper<'a>(config: &'a DwConfig, output: Option<&String>) -> &'a DwWallpaperCandidate {
+    output
+        .and_then(|output| config.output_wallpapers.get(output))
+        .unwrap_or(&config.actual_wallpaper)
+}
+
+/// Shows `desktop_path` on the lock screen and greeter targets following the
+/// desktop, and moves the ones with their own cycle with `step`. Without `step`
+/// those keep their wallpaper.
+fn update_targets(desktop_path: &str, step: Option<CandidateStep>) -> DwOperationExecutionResult {
+    let mut config = match read_config_json("config/config.json") {
+        Ok(config) => config,
+        Err(e) => {
+            return DwOperationExecutionResult {
+                success: false,
+                exit_code: 44,
+                message: Some(e.to_string()),
+            };
+        }
+    };
+
+    let mut wallpapers: Vec<(DwTargetConfig, String)> = Vec::new();
+    let mut moved = false;
+
+    for target in config.targets.iter_mut() {
+        if target.candidates.is_empty() {
+            wallpapers.push((target.clone(), desktop_path.to_string()));
+            continue;
+        }
+
+        let step = match step {
+            Some(step) => step,
+            None => continue,
+        };
+        //Sem cursor, o ciclo do target começa pelo primeiro candidato
+        let stepped = match &target.actual_wallpaper {
+            Some(actual) => step(actual, &target.candidates),
+            None => first_candidate(&target.candidates),
+        };
+        let stepped = match stepped {
+            Ok(stepped) => stepped,
+            Err(res) => return res,
+        };
+
+        wallpapers.push((target.clone(), stepped.path.clone()));
+        target.actual_wallpaper = Some(stepped);
+        moved = true;
+    }
+
+    let display = config.display.clone();
+    let dark_variants = config.dark_variants.clone();
+
+    for (target, path) in wallpapers {
+        let dark = dark_variant(&path, &dark_variants);
+
+        if let Err(e) = change_target_wallpaper(
+            &target,
+            Path::new(&path),
+            dark.as_deref().map(Path::new),
+            &display,
+        ) {
+            return DwOperationExecutionResult {
+                success: false,
+                exit_code: 46,
+                message: Some(e.to_string()),
             };
         }
     }
+
+    //Os cursores dos targets também só andam depois da troca
+    if moved {
+        if let Err(e) = write_config_json(config, "./config/config.json".into()) {
+            return DwOperationExecutionResult {
+                success: false,
+                exit_code: 45,
+                message: Some(e.to_string()),
+            };
+        }
+    }
+
+    DwOperationExecutionResult {
+        success: true,
+        exit_code: 0,
+        message: None,
+    }
+}
+
+/// Moves the cursor of `workspace` inside its own candidates and shows the new
+/// wallpaper if the workspace is `focused`.
+fn step_workspace(
+    mut config: DwConfig,
+    workspace: &str,
+    focused: Option<Workspace>,
+    step: CandidateStep,
+    write_exit_code: i32,
+) -> DwOperationExecutionResult {
+    let candidates = match config.workspaces.get(workspace) {
+        Some(candidates) if !candidates.is_empty() => candidates.clone(),
+        _ => {
+            return DwOperationExecutionResult {
+                success: false,
+                exit_code: 43,
+                message: Some(format!("Workspace {} has no wallpapers in the config", workspace)),
+            };
+        }
+    };
+
+    //Sem cursor, o workspace está mostrando o primeiro candidato
+    let actual = match config.workspace_wallpapers.get(workspace) {
+        Some(actual) => actual.clone(),
+        None => match first_candidate(&candidates) {
+            Ok(first) => first,
+            Err(res) => return res,
+        },
+    };
+    let stepped = match step(&actual, &candidates) {
+        Ok(stepped) => stepped,
+        Err(res) => return res,
+    };
+    let stepped_path = stepped.path.clone();
+    config.workspace_wallpapers.insert(workspace.to_string(), stepped);
+
+    let supports_outputs = config_supports_outputs(&config);
+
+    //Como no step_cycle, o cursor só anda se a troca der certo
+    if let Some(focused) = focused.filter(|focused| focused.name == workspace) {
+        let res = apply_wallpaper(
+            &stepped_path,
+            None,
+            Some(&focused.output).filter(|_| supports_outputs),
+        );
+        if !res.success {
+            return res;
+        }
+    }
+
+    match write_config_json(config, "./config/config.json".into()) {
+        Ok(_) => DwOperationExecutionResult {
+            success: true,
+            exit_code: 0,
+            message: None,
+        },
+        Err(e) => DwOperationExecutionResult {
+            success: false,
+            exit_code: write_exit_code,
+            message: Some(e.to_string()),
+        },
+    }
 }
 
-pub fn previous() -> DwOperationExecutionResult {
-    let mut config = match read_config_json("config/config.json") {
-        Ok(config) => config,
-        Err(e) => {
-            return DwOperationExecutionResult {
-                success: false,
-                exit_code: 13,
-                message: Some(e.to_string()),
-            };
-        }
-    };
+/// Whether the backend used with `config` can give each output its own image.
+fn config_supports_outputs(config: &DwConfig) -> bool {
+    build_registry(&config.display, config.backend.as_ref())
+        .select(forced_backend().as_deref())
+        .is_ok_and(|backend| backend.supports_outputs())
+}
+
+fn previous_candidate(
+    actual: &DwWallpaperCandidate,
+    candidates: &[String],
+) -> Result<DwWallpaperCandidate, DwOperationExecutionResult> {
+    //Uma config sem candidatos não tem para onde andar
+    if candidates.is_empty() {
+        return Err(DwOperationExecutionResult {
+            success: false,
+            exit_code: 57,
+            message: Some("No candidates".to_string()),
+        });
+    }
 
     //Config atual
-    let actual_wallpaper_path: &Path = Path::new(&config.actual_wallpaper.path);
-    let actual_wallpaper_index: usize = config.actual_wallpaper.index;
-    let actual_wallpaper_child: bool = config.actual_wallpaper.child;
+    let actual_wallpaper_path: &Path = Path::new(&actual.path);
+    let actual_wallpaper_index: usize = actual.index;
+    let actual_wallpaper_child: bool = actual.child;
 
     //Tentando obter informações
     let (actual_wallpaper_dir_path, actual_wallpaper_file_name) =
@@ -272,19 +1009,19 @@ pub fn previous() -> DwOperationExecutionResult {
             Some(dir_path) => match actual_wallpaper_path.file_name() {
                 Some(file_name) => (dir_path, file_name),
                 None => {
-                    return DwOperationExecutionResult {
+                    return Err(DwOperationExecutionResult {
                         success: false,
                         exit_code: 14,
                         message: Some("Error getting file name from path".into()),
-                    };
+                    });
                 }
             },
             None => {
-                return DwOperationExecutionResult {
+                return Err(DwOperationExecutionResult {
                     success: false,
                     exit_code: 15,
                     message: Some("Error getting parent directory from path".into()),
-                };
+                });
             }
         };
 
@@ -304,42 +1041,42 @@ pub fn previous() -> DwOperationExecutionResult {
             actual_wallpaper_file_name.to_str().unwrap(),
         ) {
             Ok(index) => index,
-            Err(_) => config.actual_wallpaper.sub_index
+            Err(_) => actual.sub_index
         };
 
         //Conseguindo o subindex...
         if actual_wallpaper_sub_index == 0 {
 
             if actual_wallpaper_index == 0{
-                previous_wallpaper_index = config.candidates.len() - 1;
+                previous_wallpaper_index = candidates.len() - 1;
             }else {
                 previous_wallpaper_index = actual_wallpaper_index - 1; //Sabendo que vamos sair do conjunto atual de candidatos, temos que subtrair o index primário
             }
 
             //Se o subindex for zero, o conjunto de imagens atual muda, sendo necessário empregar lógica para defini-lo
 
-            if Path::new(&config.candidates[previous_wallpaper_index]).is_dir() {
+            if Path::new(&candidates[previous_wallpaper_index]).is_dir() {
                 previous_wallpaper_child = true; //Já temos certeza que o wallpaper anterior será um child
 
                 //Tenta listar os candidatos na pasta anterior para definir o Wallpaper que será setado como a última imagem do diretório anterior (assim como seu subindex)
                 match list_images_in_directory(Path::new(
-                    &config.candidates[previous_wallpaper_index],
+                    &candidates[previous_wallpaper_index],
                 )) {
                     Ok(image_paths) => {
                         previous_wallpaper_path = image_paths.last().unwrap().clone(); //O candidato anterior equivale à última imagem da pasta anterior
                         previous_wallpaper_sub_index = image_paths.len() - 1; //O subindex da última imagem do diretório anterior
                     }
                     Err(e) => {
-                        return DwOperationExecutionResult {
+                        return Err(DwOperationExecutionResult {
                             success: false,
                             exit_code: 16,
                             message: Some(e.to_string()),
-                        };
+                        });
                     }
                 }
             } else {
                 previous_wallpaper_child = false; //Aqui já sabemos que o anterior não é um diretório, pois esse else é um desvio condicional justamente disso
-                previous_wallpaper_path = config.candidates[previous_wallpaper_index].clone();
+                previous_wallpaper_path = candidates[previous_wallpaper_index].clone();
                 previous_wallpaper_sub_index = 0;
             }
         } else {
@@ -356,74 +1093,63 @@ pub fn previous() -> DwOperationExecutionResult {
         }   
     }else{
         if actual_wallpaper_index == 0{
-            previous_wallpaper_index = config.candidates.len() - 1;
+            previous_wallpaper_index = candidates.len() - 1;
         }else{
             previous_wallpaper_index = actual_wallpaper_index - 1;
         }
 
-        if Path::new(&config.candidates[previous_wallpaper_index]).is_dir(){
+        if Path::new(&candidates[previous_wallpaper_index]).is_dir(){
             previous_wallpaper_child = true;
 
             match list_images_in_directory(Path::new(
-                &config.candidates[previous_wallpaper_index],
+                &candidates[previous_wallpaper_index],
             )) {
                 Ok(image_paths) => {
                     previous_wallpaper_path = image_paths.last().unwrap().clone();
                     previous_wallpaper_sub_index = image_paths.len() - 1;
                 }
                 Err(e) => {
-                    return DwOperationExecutionResult {
+                    return Err(DwOperationExecutionResult {
                         success: false,
                         exit_code: 17,
                         message: Some(e.to_string()),
-                    };
+                    });
                 }
             }
 
         }else{
             previous_wallpaper_child = false;
             previous_wallpaper_sub_index = 0;
-            previous_wallpaper_path = config.candidates[previous_wallpaper_index].clone();
+            previous_wallpaper_path = candidates[previous_wallpaper_index].clone();
         }
     }
 
-    config.actual_wallpaper.path = previous_wallpaper_path.clone();
-    config.actual_wallpaper.date_set = Local::now();
-    config.actual_wallpaper.index = previous_wallpaper_index;
-    config.actual_wallpaper.child = previous_wallpaper_child;
-    config.actual_wallpaper.sub_index = previous_wallpaper_sub_index;
-
-    match write_config_json(config, "./config/config.json".into()) {
-        Ok(_) => {
-            return set_wallpaper(&previous_wallpaper_path);
-        }
-
-        Err(e) => {
-            return DwOperationExecutionResult {
-                success: false,
-                exit_code: 18,
-                message: Some(e.to_string()),
-            };
-        }
-    }
+    Ok(DwWallpaperCandidate {
+        index: previous_wallpaper_index,
+        path: previous_wallpaper_path,
+        date_set: Local::now(),
+        child: previous_wallpaper_child,
+        sub_index: previous_wallpaper_sub_index,
+    })
 }
 
-pub fn next()-> DwOperationExecutionResult{
-    let mut config = match read_config_json("config/config.json") {
-        Ok(config) => config,
-        Err(e) => {
-            return DwOperationExecutionResult {
-                success: false,
-                exit_code: 19,
-                message: Some(e.to_string()),
-            };
-        }
-    };
+fn next_candidate(
+    actual: &DwWallpaperCandidate,
+    candidates: &[String],
+) -> Result<DwWallpaperCandidate, DwOperationExecutionResult> {
+    //Uma config sem candidatos não tem para onde andar
+    if candidates.is_empty() {
+        return Err(DwOperationExecutionResult {
+            success: false,
+            exit_code: 57,
+            message: Some("No candidates".to_string()),
+        });
+    }
 
     //Config atual
-    let actual_wallpaper_path: &Path = Path::new(&config.actual_wallpaper.path);
-    let actual_wallpaper_index: usize = config.actual_wallpaper.index;
-    let actual_wallpaper_child: bool = config.actual_wallpaper.child;
+    let actual_wallpaper_path: &Path = Path::new(&actual.path);
+    let actual_wallpaper_index: usize = actual.index;
+    let actual_wallpaper_child: bool = actual.child;
 
     //Tentando obter informações
     let (actual_wallpaper_dir_path, actual_wallpaper_file_name) =
@@ -431,19 +1157,19 @@ pub fn next()-> DwOperationExecutionResult{
             Some(dir_path) => match actual_wallpaper_path.file_name() {
                 Some(file_name) => (dir_path, file_name),
                 None => {
-                    return DwOperationExecutionResult {
+                    return Err(DwOperationExecutionResult {
                         success: false,
                         exit_code: 20,
                         message: Some("Error getting file name from path".into()),
-                    };
+                    });
                 }
             },
             None => {
-                return DwOperationExecutionResult {
+                return Err(DwOperationExecutionResult {
                     success: false,
                     exit_code: 21,
                     message: Some("Error getting parent directory from path".into()),
-                };
+                });
             }
         };
 
@@ -461,19 +1187,19 @@ pub fn next()-> DwOperationExecutionResult{
             actual_wallpaper_file_name.to_str().unwrap(),
         ) {
             Ok(index) => index,
-            Err(_) => config.actual_wallpaper.sub_index
+            Err(_) => actual.sub_index
         };
 
-        let num_of_candidates_in_dir = match list_images_in_directory(&Path::new(&config.candidates[actual_wallpaper_index])) {
+        let num_of_candidates_in_dir = match list_images_in_directory(Path::new(&candidates[actual_wallpaper_index])) {
             Ok(image_paths) => {
                 image_paths.len()
             },
             Err(e) => {
-                return DwOperationExecutionResult {
+                return Err(DwOperationExecutionResult {
                     success: false,
                     exit_code: 22,
                     message: Some(e.to_string()),
-                };
+                });
             }
         };
 
@@ -485,7 +1211,7 @@ pub fn next()-> DwOperationExecutionResult{
         //Conseguindo o subindex...
         if actual_wallpaper_sub_index == num_of_candidates_in_dir - 1 {
 
-            if actual_wallpaper_index == config.candidates.len() - 1{
+            if actual_wallpaper_index == candidates.len() - 1{
                 next_wallpaper_index = 0;
             }else {
                 next_wallpaper_index = actual_wallpaper_index + 1; //Sabendo que vamos sair do conjunto atual de candidatos, temos que subtrair o index primário
@@ -493,21 +1219,21 @@ pub fn next()-> DwOperationExecutionResult{
             
             next_wallpaper_sub_index = 0;
 
-            if Path::new(&config.candidates[next_wallpaper_index]).is_dir() {
-                next_wallpaper_path = match found_wpp_path_by_index_in_directory(Path::new(&config.candidates[next_wallpaper_index]), 0){
+            if Path::new(&candidates[next_wallpaper_index]).is_dir() {
+                next_wallpaper_path = match found_wpp_path_by_index_in_directory(Path::new(&candidates[next_wallpaper_index]), 0){
                     Ok(path) => path,
                     Err(e) => {
-                        return DwOperationExecutionResult {
+                        return Err(DwOperationExecutionResult {
                             success: false,
                             exit_code: 23,
                             message: Some(e.to_string()),
-                        }
+                        })
                     }
                 };
                 next_wallpaper_child = true; //Já temos certeza que o wallpaper anterior será um child
 
             } else {
-                next_wallpaper_path = config.candidates[next_wallpaper_index].clone();
+                next_wallpaper_path = candidates[next_wallpaper_index].clone();
                 next_wallpaper_child = false; //Aqui já sabemos que o posterior não é um diretório, pois esse else é um desvio condicional justamente disso
             }
 
@@ -515,63 +1241,51 @@ pub fn next()-> DwOperationExecutionResult{
             next_wallpaper_child = true; //Se não estamos saindo de um conjunto de imagens, o Wallpaper a ser setado ainda é um child
             next_wallpaper_index = actual_wallpaper_index; //Se não estamos saindo de um conjunto, o index não muda
             next_wallpaper_sub_index = actual_wallpaper_sub_index + 1; //Se o subindex do atual não for zero, significa que o subindex de quem será setado é o atual mais 1 (porque queremos o posterior)
-            next_wallpaper_path = match found_wpp_path_by_index_in_directory(Path::new(&config.candidates[next_wallpaper_index]), next_wallpaper_sub_index){
+            next_wallpaper_path = match found_wpp_path_by_index_in_directory(Path::new(&candidates[next_wallpaper_index]), next_wallpaper_sub_index){
                 Ok(path) => path,
                 Err(e) => {
-                    return DwOperationExecutionResult {
+                    return Err(DwOperationExecutionResult {
                         success: false,
                         exit_code: 24,
                         message: Some(e.to_string()),
-                    }
+                    })
                 }
             };
         }   
     }else{
-        if actual_wallpaper_index == config.candidates.len() - 1{
+        if actual_wallpaper_index == candidates.len() - 1{
             next_wallpaper_index = 0;
         }else{
             next_wallpaper_index = actual_wallpaper_index + 1;
         }
 
-        next_wallpaper_path = match found_wpp_path_by_index_in_directory(Path::new(&config.candidates[next_wallpaper_index]), 0){
-            Ok(path) => path,
-            Err(e) => {
-                return DwOperationExecutionResult {
-                    success: false,
-                    exit_code: 25,
-                    message: Some(e.to_string()),
-                }
-            }
-        };
-
         next_wallpaper_sub_index = 0;
 
-        if Path::new(&config.candidates[next_wallpaper_index]).is_dir(){
-            next_wallpaper_child = true;
-        }else{
-            next_wallpaper_child = false;
-        }
-    }
-
-    config.actual_wallpaper.path = next_wallpaper_path.clone();
-    config.actual_wallpaper.date_set = Local::now();
-    config.actual_wallpaper.index = next_wallpaper_index;
-    config.actual_wallpaper.child = next_wallpaper_child;
-    config.actual_wallpaper.sub_index = next_wallpaper_sub_index;
-
-    match write_config_json(config, "./config/config.json".into()) {
-        Ok(_) => {
-            return set_wallpaper(&next_wallpaper_path);
-        }
+        next_wallpaper_child = Path::new(&candidates[next_wallpaper_index]).is_dir();
 
-        Err(e) => {
-            return DwOperationExecutionResult {
-                success: false,
-                exit_code: 26,
-                message: Some(e.to_string()),
+        if next_wallpaper_child {
+            next_wallpaper_path = match found_wpp_path_by_index_in_directory(Path::new(&candidates[next_wallpaper_index]), 0){
+                Ok(path) => path,
+                Err(e) => {
+                    return Err(DwOperationExecutionResult {
+                        success: false,
+                        exit_code: 25,
+                        message: Some(e.to_string()),
+                    })
+                }
             };
+        } else {
+            next_wallpaper_path = candidates[next_wallpaper_index].clone();
         }
     }
+
+    Ok(DwWallpaperCandidate {
+        index: next_wallpaper_index,
+        path: next_wallpaper_path,
+        date_set: Local::now(),
+        child: next_wallpaper_child,
+        sub_index: next_wallpaper_sub_index,
+    })
 }
 
 pub fn reset() -> DwOperationExecutionResult {
@@ -586,53 +1300,86 @@ pub fn reset() -> DwOperationExecutionResult {
         }
     };
 
+    config.actual_wallpaper = match first_candidate(&config.candidates) {
+        Ok(first) => first,
+        Err(res) => return res,
+    };
+    config.output_wallpapers.clear();
+    config.workspace_wallpapers.clear();
+
+    let reset_wallpaper_path = config.actual_wallpaper.path.clone();
+
+    match write_config_json(config, "./config/config.json".into()) {
+        Ok(_) => {
+            let res = apply_wallpaper(&reset_wallpaper_path, None, None);
+            if !res.success {
+                return res;
+            }
+
+            update_targets(
+                &reset_wallpaper_path,
+                Some(&|_, candidates| first_candidate(candidates)),
+            )
+        }
+        Err(e) => DwOperationExecutionResult {
+            success: false,
+            exit_code: 30,
+            message: Some(e.to_string()),
+        },
+    }
+}
+
+/// Cursor on the first wallpaper of `candidates`.
+fn first_candidate(candidates: &[String]) -> Result<DwWallpaperCandidate, DwOperationExecutionResult> {
+    //Uma config sem candidatos não tem para onde andar
+    if candidates.is_empty() {
+        return Err(DwOperationExecutionResult {
+            success: false,
+            exit_code: 57,
+            message: Some("No candidates".to_string()),
+        });
+    }
+
     let reset_wallpaper_path: String;
     let reset_wallpaper_child: bool;
     let reset_wallpaper_sub_index: usize;
 
-    if Path::new(&config.candidates[0]).is_dir() {
+    if Path::new(&candidates[0]).is_dir() {
         reset_wallpaper_child = true;
         reset_wallpaper_sub_index = 0;
-        reset_wallpaper_path = match list_images_in_directory(Path::new(&config.candidates[0])) {
+        reset_wallpaper_path = match list_images_in_directory(Path::new(&candidates[0])) {
             Ok(image_paths) => {
                 if !image_paths.is_empty() {
                     image_paths[0].clone()
                 } else {
-                    return DwOperationExecutionResult {
+                    return Err(DwOperationExecutionResult {
                         success: false,
                         exit_code: 28,
                         message: Some("The directory is empty".to_string()),
-                    };
+                    });
                 }
             }
             Err(e) => {
-                return DwOperationExecutionResult {
+                return Err(DwOperationExecutionResult {
                     success: false,
                     exit_code: 29,
                     message: Some(e.to_string()),
-                };
+                });
             }
         };
     } else {
         reset_wallpaper_child = false;
         reset_wallpaper_sub_index = 0;
-        reset_wallpaper_path = config.candidates[0].clone();
+        reset_wallpaper_path = candidates[0].clone();
     }
 
-    config.actual_wallpaper.path = reset_wallpaper_path.clone();
-    config.actual_wallpaper.date_set = Local::now();
-    config.actual_wallpaper.index = 0;
-    config.actual_wallpaper.child = reset_wallpaper_child;
-    config.actual_wallpaper.sub_index = reset_wallpaper_sub_index;
-
-    match write_config_json(config, "./config/config.json".into()) {
-        Ok(_) => set_wallpaper(&reset_wallpaper_path),
-        Err(e) => DwOperationExecutionResult {
-            success: false,
-            exit_code: 30,
-            message: Some(e.to_string()),
-        },
-    }
+    Ok(DwWallpaperCandidate {
+        index: 0,
+        path: reset_wallpaper_path,
+        date_set: Local::now(),
+        child: reset_wallpaper_child,
+        sub_index: reset_wallpaper_sub_index,
+    })
 }
 
 pub fn on() -> DwOperationExecutionResult{  
@@ -647,17 +1394,40 @@ pub fn on() -> DwOperationExecutionResult{
         }
     };
 
-    let action: String;
+    #[cfg(target_os = "linux")]
+    {
+        //Chamar `on` de novo só substitui o agendamento existente
+        let scheduler = match schedulers::enable(config.scheduler, &config.time_config) {
+            Ok(scheduler) => scheduler,
+            Err(e) => {
+                return DwOperationExecutionResult {
+                    success: false,
+                    exit_code: 34,
+                    message: Some(e.to_string()),
+                };
+            }
+        };
 
-    #[cfg(target_os = "linux")]{
-        action = "~/.dw/bin/dw next".to_string();
-    }
+        if let Err(e) = set_enabled(true) {
+            return DwOperationExecutionResult {
+                success: false,
+                exit_code: 52,
+                message: Some(e.to_string()),
+            };
+        }
 
+        DwOperationExecutionResult {
+            success: true,
+            exit_code: 0,
+            message: Some(format!("Wallpaper changes scheduled with {}", scheduler)),
+        }
+    }
+    
     #[cfg(target_os = "windows")]
     {
-        action = match env::var("USERPROFILE") {
+        let action = match env::var("USERPROFILE") {
             Ok(user_profile) => format!(
-                "powershell -Command 'cd {}\\.dwr ; .\\daily-wallpapers-rust.exe next'",
+                "powershell -Command 'cd {}\\.dwr ; .\\daily-wallpapers-rust.exe tick'",
                 user_profile
             ),
             Err(e) => {
@@ -668,74 +1438,20 @@ pub fn on() -> DwOperationExecutionResult{
                 };
             },
         };
-    }
-    
-    
-    
-    // Converting `action` to `&str`
-    let action: &str = &action;
 
-    //println!("{}", scheduler_string);
-    #[cfg(target_os = "linux")]
-    {
-        // Chama a função `generate_schedule` e obtém a string do cron
-        let cron_string = generate_schedule(config.time_config.preset, config.time_config.interval, "DWR", action);
-    
-        // Função para adicionar a entrada ao cron
-        fn add_cron_entry(entry: &str) -> Result<(), Box<dyn std::error::Error>> {
-            let status = Command::new("sh")
-                .arg("scripts/linux/cron_adder.sh")
-                .arg(entry)
-                .status()?;
-    
-            if status.success() {
-                Ok(())
-            } else {
-                Err("Failed to add cron entry".into())
-            }
-        }
-    
-        match config.time_config.preset {
-            DwPreset::DAY => {
+        let schedule = match Schedule::from_config(&config.time_config)
+            .and_then(|schedule| generate_schedule(&schedule, "DWR", &action))
+        {
+            Ok(schedule) => schedule,
+            Err(e) => {
                 return DwOperationExecutionResult {
                     success: false,
                     exit_code: 33,
-                    message: Some("Unsupported preset".to_string()),
-                };
-            }
-    
-            DwPreset::HOUR | DwPreset::MINUTE => {
-                match add_cron_entry(cron_string.as_str()) {
-                    Ok(()) => {
-                        return DwOperationExecutionResult {
-                            success: true,
-                            exit_code: 0,
-                            message: Some("Cron entry added successfully".to_string()),
-                        };
-                    }
-                    Err(e) => {
-                        return DwOperationExecutionResult {
-                            success: false,
-                            exit_code: 34,
-                            message: Some(e.to_string()),
-                        };
-                    }
-                }
-            }
-    
-            _ => {
-                return DwOperationExecutionResult {
-                    success: false,
-                    exit_code: 35,
-                    message: Some("Unsupported preset".to_string()),
+                    message: Some(e.to_string()),
                 };
             }
-        }
-    }
-    
-    #[cfg(target_os = "windows")]
-    {
-        let (command, args) = generate_schedule(config.time_config.preset, config.time_config.interval, "DWR", &action);
+        };
+        let (command, args) = schedule;
     
         let output = Command::new(&command)
             .args(&args)
@@ -771,48 +1487,27 @@ pub fn on() -> DwOperationExecutionResult{
 pub fn off() -> DwOperationExecutionResult {
 
     #[cfg(target_os = "linux")]{
+        //Remove o agendamento de todos os agendadores disponíveis
+        if let Err(e) = schedulers::disable(None) {
+            return DwOperationExecutionResult {
+                success: false,
+                exit_code: 37,
+                message: Some(format!("Erro ao remover o agendamento: {}", e)),
+            };
+        }
 
-        let config = match read_config_json("config/config.json") {
-            Ok(config) => config,
-            Err(e) => {
-                return DwOperationExecutionResult {
-                    success: false,
-                    exit_code: 36,
-                    message: Some(e.to_string()),
-                };
-            }
-        };
-
-        fn remove_cron_entry(entry: &str) -> Result<(), Box<dyn std::error::Error>> {
-            let status = Command::new("sh")
-                .arg("scripts/linux/cron_remover.sh")
-                .arg(entry)
-                .status()?;
-            
-            if status.success() {
-                Ok(())
-            } else {
-                Err("Failed to add cron entry".into())
-            }
+        if let Err(e) = set_enabled(false) {
+            return DwOperationExecutionResult {
+                success: false,
+                exit_code: 52,
+                message: Some(e.to_string()),
+            };
         }
-        
-        let scheduler_string = generate_schedule(config.time_config.preset, config.time_config.interval, "DWR", "~/.dw/bin/dw next");
 
-        match remove_cron_entry(scheduler_string.as_str()) {
-            Ok(()) => {
-                DwOperationExecutionResult {
-                    success: true,
-                    exit_code: 0,
-                    message: Some("Entrada do cron removida com sucesso".to_string()),
-                }
-            }
-            Err(e) => {
-                DwOperationExecutionResult {
-                    success: false,
-                    exit_code: 37,
-                    message: Some(format!("Erro ao remover a entrada do cron: {}", e)),
-                }
-            }
+        DwOperationExecutionResult {
+            success: true,
+            exit_code: 0,
+            message: Some("Agendamento removido com sucesso".to_string()),
         }
     }
 
@@ -847,3 +1542,157 @@ pub fn off() -> DwOperationExecutionResult {
         }   
     }
 }
+
+/// Saves the flag followed by `dw daemon` and lets a running daemon know.
+#[cfg(target_os = "linux")]
+fn set_enabled(enabled: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = read_config_json("config/config.json")?;
+    if config.enabled == enabled {
+        return Ok(());
+    }
+
+    config.enabled = enabled;
+    write_config_json(config, "./config/config.json".into())?;
+    //Sem daemon rodando não há quem avisar
+    let _ = crate::daemon::wake();
+
+    Ok(())
+}
+
+pub fn daemon() -> DwOperationExecutionResult {
+    #[cfg(target_os = "linux")]
+    let res = {
+        import_session_environment();
+
+        //O daemon assume as trocas, um cron ou timer instalado antes trocaria de novo
+        if let Err(e) = schedulers::disable(Some("daemon")) {
+            eprintln!("{}", e);
+        }
+
+        //A troca agendada e a troca de tema não aplicam ao mesmo tempo
+        let applying = Arc::new(Mutex::new(()));
+
+        //Sem portal o daemon segue só com o agendamento
+        let mut monitor = match color_scheme::monitor_color_scheme() {
+            Ok(child) => Some(child),
+            Err(e) => {
+                eprintln!("{}", e);
+                None
+            }
+        };
+        if let Some(signals) = monitor.as_mut().and_then(|child| child.stdout.take()) {
+            let applying = Arc::clone(&applying);
+            thread::spawn(move || {
+                let res = color_scheme::follow_color_scheme(signals, || {
+                    let _applying = applying.lock().unwrap_or_else(PoisonError::into_inner);
+                    let res = reapply_wallpapers();
+                    //Um wallpaper com problema não deve derrubar o watcher
+                    if !res.success {
+                        eprintln!("{:?}", res);
+                    }
+
+                    Ok(())
+                });
+                if let Err(e) = res {
+                    eprintln!("{}", e);
+                }
+            });
+        }
+
+        //Um erro é checado de novo a cada minuto, mas só é repetido quando muda
+        let mut last_error: Option<String> = None;
+
+        let res = crate::daemon::run_daemon(|| {
+            if let Some(status) = monitor
+                .as_mut()
+                .and_then(|child| child.try_wait().ok().flatten())
+            {
+                eprintln!("Error: The gdbus signal stream ended ({}).", status);
+                monitor = None;
+            }
+
+            let now = Local::now();
+            let due = read_config_json("config/config.json").and_then(|config| {
+                if !config.enabled {
+                    return Ok(None);
+                }
+
+                Schedule::from_config(&config.time_config)?
+                    .next_after(last_change(&config))
+                    .map(Some)
+                    .ok_or_else(|| {
+                        "Error: The daemon can't follow `cron` and `on_calendar` expressions.".into()
+                    })
+            });
+
+            let due = match due {
+                Ok(due) => due,
+                Err(e) => {
+                    if last_error.as_ref() != Some(&e.to_string()) {
+                        eprintln!("{}", e);
+                        last_error = Some(e.to_string());
+                    }
+                    return now + crate::daemon::RECHECK;
+                }
+            };
+            last_error = None;
+
+            match due {
+                Some(due) if due <= now => {
+                    let _applying = applying.lock().unwrap_or_else(PoisonError::into_inner);
+                    let res = tick(false);
+                    if !res.success {
+                        //Tenta de novo na próxima checagem, daqui a um minuto
+                        eprintln!("{:?}", res);
+                        return now + crate::daemon::RECHECK;
+                    }
+
+                    //No dry-run a data da troca não é salva
+                    if crate::core_functions::is_dry_run() {
+                        return now + crate::daemon::RECHECK;
+                    }
+
+                    //Checa de novo já com a data da troca salva
+                    Local::now()
+                }
+                Some(due) => due,
+                //Desligado só olha a config de novo em um minuto ou quando on/off acordam o daemon
+                None => now + crate::daemon::RECHECK,
+            }
+        });
+
+        //O gdbus não pode sobreviver ao daemon
+        if let Some(mut child) = monitor {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+
+        res
+    };
+    #[cfg(not(target_os = "linux"))]
+    let res: Result<(), Box<dyn std::error::Error>> =
+        Err("Error: The daemon needs a Linux session.".into());
+
+    match res {
+        Ok(_) => DwOperationExecutionResult {
+            success: true,
+            exit_code: 0,
+            message: None,
+        },
+        Err(e) => DwOperationExecutionResult {
+            success: false,
+            exit_code: 53,
+            message: Some(e.to_string()),
+        },
+    }
+}
+
+/// When the wallpaper last changed, on any output or workspace.
+fn last_change(config: &DwConfig) -> DateTime<Local> {
+    config
+        .output_wallpapers
+        .values()
+        .chain(config.workspace_wallpapers.values())
+        .map(|candidate| candidate.date_set)
+        .fold(config.actual_wallpaper.date_set, DateTime::max)
+}
@@ -0,0 +1,459 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// src/schedule.rs
+
+use crate::core_models::{DwPreset, DwTimeConfig};
+use chrono::{DateTime, Datelike, Days, Duration, Local, TimeZone, Timelike};
+use std::error::Error;
+
+const WEEKDAYS: [&str; 7] = [
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+const CRON_MACROS: [&str; 8] = [
+    "@reboot",
+    "@yearly",
+    "@annually",
+    "@monthly",
+    "@weekly",
+    "@daily",
+    "@midnight",
+    "@hourly",
+];
+
+/// How often `tick` also runs to make the changes a scheduler missed while
+/// the machine was off or asleep.
+const CATCH_UP_MINUTES: u8 = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord)]
+pub struct TimeOfDay {
+    pub hour: u8,
+    pub minute: u8,
+}
+
+/// When the wallpaper changes, checked and normalized from a `DwTimeConfig`.
+///
+/// Weekdays are indexes into `WEEKDAYS`, an empty list meaning every day.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Schedule {
+    /// Every `n` minutes.
+    Minutes(u8),
+    /// Every `n` hours, on the hour.
+    Hours(u8),
+    /// Every `interval` days, at `at`.
+    Days { interval: u8, at: TimeOfDay },
+    /// At each of `times`, on `weekdays`.
+    Times {
+        times: Vec<TimeOfDay>,
+        weekdays: Vec<usize>,
+    },
+    /// Expressions handed as they are to the scheduler that understands them.
+    Raw {
+        cron: Option<String>,
+        on_calendar: Option<String>,
+    },
+}
+
+/// Parses a time of day like `07:00` or `7:00`.
+fn parse_time(time: &str) -> Result<TimeOfDay, Box<dyn Error>> {
+    let invalid = || format!("Error: Invalid time {:?}, expected HH:MM.", time);
+    let (hour, minute) = time.trim().split_once(':').ok_or_else(invalid)?;
+    let hour: u8 = hour.parse().map_err(|_| invalid())?;
+    let minute_digits = minute.len();
+    let minute: u8 = minute.parse().map_err(|_| invalid())?;
+
+    if hour > 23 || minute > 59 || minute_digits != 2 {
+        return Err(invalid().into());
+    }
+
+    Ok(TimeOfDay { hour, minute })
+}
+
+/// Parses a weekday, by name or by its first three letters.
+fn parse_weekday(weekday: &str) -> Result<usize, Box<dyn Error>> {
+    let weekday = weekday.trim().to_lowercase();
+
+    WEEKDAYS
+        .iter()
+        .position(|name| *name == weekday || name[..3] == weekday)
+        .ok_or_else(|| format!("Error: Invalid weekday {:?}.", weekday).into())
+}
+
+fn validate_cron(expression: &str) -> Result<(), Box<dyn Error>> {
+    let fields: Vec<&str> = expression.split_whitespace().collect();
+    // `%` is a line break for cron, and other characters have no meaning there.
+    let valid = match fields.as_slice() {
+        [macro_name] if macro_name.starts_with('@') => CRON_MACROS.contains(macro_name),
+        _ => {
+            fields.len() == 5
+                && fields.iter().all(|field| {
+                    field
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || "*/,-".contains(c))
+                })
+        }
+    };
+
+    if !valid {
+        return Err(format!(
+            "Error: Invalid cron expression {:?}, expected 5 fields or a macro like @daily.",
+            expression
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Checks an `OnCalendar=` expression with `systemd-analyze` when there is one.
+fn validate_on_calendar(expression: &str) -> Result<(), Box<dyn Error>> {
+    if expression.trim().is_empty() || expression.contains('\n') {
+        return Err(format!("Error: Invalid OnCalendar expression {:?}.", expression).into());
+    }
+
+    #[cfg(target_os = "linux")]
+    if crate::backends::find_in_path("systemd-analyze").is_some() {
+        crate::backends::query("systemd-analyze", &["calendar", expression])
+            .map_err(|_| format!("Error: Invalid OnCalendar expression {:?}.", expression))?;
+    }
+
+    Ok(())
+}
+
+/// First of `times` after `last`, on one of `weekdays` (any day when empty).
+fn next_time(
+    last: DateTime<Local>,
+    times: &[TimeOfDay],
+    weekdays: &[usize],
+) -> Option<DateTime<Local>> {
+    // A week and a day always reaches the next matching weekday.
+    for day in last.date_naive().iter_days().take(8) {
+        let weekday = day.weekday().num_days_from_monday() as usize;
+        if !weekdays.is_empty() && !weekdays.contains(&weekday) {
+            continue;
+        }
+
+        for time in times {
+            // Times skipped by a DST change don't exist that day.
+            let at = day
+                .and_hms_opt(time.hour.into(), time.minute.into(), 0)
+                .and_then(|at| Local.from_local_datetime(&at).earliest());
+
+            match at {
+                Some(at) if at > last => return Some(at),
+                _ => continue,
+            }
+        }
+    }
+
+    None
+}
+
+impl Schedule {
+    pub fn from_config(time_config: &DwTimeConfig) -> Result<Schedule, Box<dyn Error>> {
+        if time_config.cron.is_some() || time_config.on_calendar.is_some() {
+            if !time_config.times.is_empty() || !time_config.weekdays.is_empty() {
+                return Err(
+                    "Error: `cron` and `on_calendar` can't be combined with `times` or `weekdays`."
+                        .into(),
+                );
+            }
+            if let Some(cron) = &time_config.cron {
+                validate_cron(cron)?;
+            }
+            if let Some(on_calendar) = &time_config.on_calendar {
+                validate_on_calendar(on_calendar)?;
+            }
+
+            return Ok(Schedule::Raw {
+                cron: time_config.cron.clone(),
+                on_calendar: time_config.on_calendar.clone(),
+            });
+        }
+
+        let mut weekdays = time_config
+            .weekdays
+            .iter()
+            .map(|weekday| parse_weekday(weekday))
+            .collect::<Result<Vec<usize>, _>>()?;
+        weekdays.sort();
+        weekdays.dedup();
+
+        if !time_config.times.is_empty() {
+            let mut times = time_config
+                .times
+                .iter()
+                .map(|time| parse_time(time))
+                .collect::<Result<Vec<TimeOfDay>, _>>()?;
+            times.sort();
+            times.dedup();
+
+            return Ok(Schedule::Times { times, weekdays });
+        }
+
+        let interval = time_config.interval;
+        if interval == 0 {
+            return Err("Error: The interval must be at least 1.".into());
+        }
+        if time_config.at.is_some() && !matches!(time_config.preset, DwPreset::DAY) {
+            return Err("Error: `at` only applies to the DAY preset.".into());
+        }
+
+        match time_config.preset {
+            DwPreset::MINUTE | DwPreset::HOUR if !weekdays.is_empty() => {
+                Err("Error: `weekdays` only apply to the DAY preset and to `times`.".into())
+            }
+            DwPreset::MINUTE => Ok(Schedule::Minutes(interval)),
+            DwPreset::HOUR => Ok(Schedule::Hours(interval)),
+            DwPreset::DAY => {
+                let at = match &time_config.at {
+                    Some(at) => parse_time(at)?,
+                    None => TimeOfDay { hour: 0, minute: 0 },
+                };
+
+                if weekdays.is_empty() {
+                    Ok(Schedule::Days { interval, at })
+                } else if interval == 1 {
+                    Ok(Schedule::Times {
+                        times: vec![at],
+                        weekdays,
+                    })
+                } else {
+                    Err("Error: `weekdays` can't be combined with a DAY interval above 1.".into())
+                }
+            }
+        }
+    }
+
+    /// When the change following one made at `last` is due, `None` for raw
+    /// expressions, which only their own scheduler understands.
+    ///
+    /// The MINUTE and HOUR presets count from `last`, days and times fall on
+    /// the clock, every `n` days at the `n`th `at` following `last`. Schedulers run on whole minutes and the change takes a moment,
+    /// so `last` counts from the start of its minute.
+    pub fn next_after(&self, last: DateTime<Local>) -> Option<DateTime<Local>> {
+        let last = last
+            .with_second(0)
+            .and_then(|last| last.with_nanosecond(0))
+            .unwrap_or(last);
+
+        match self {
+            Schedule::Minutes(n) => Some(last + Duration::minutes((*n).into())),
+            Schedule::Hours(n) => Some(last + Duration::hours((*n).into())),
+            Schedule::Days { interval, at } => next_time(last, std::slice::from_ref(at), &[])?
+                .checked_add_days(Days::new(u64::from(*interval) - 1)),
+            Schedule::Times { times, weekdays } => next_time(last, times, weekdays),
+            Schedule::Raw { .. } => None,
+        }
+    }
+
+    /// How many changes came due after one made at `last`, up to `now` and
+    /// counting at most `limit`. `None` for raw expressions.
+    pub fn due_changes(
+        &self,
+        last: DateTime<Local>,
+        now: DateTime<Local>,
+        limit: usize,
+    ) -> Option<usize> {
+        let mut due = self.next_after(last)?;
+        let mut changes = 0;
+
+        while due <= now && changes < limit {
+            changes += 1;
+            due = match self.next_after(due) {
+                Some(next) => next,
+                None => break,
+            };
+        }
+
+        Some(changes)
+    }
+
+    /// `dw` command a scheduler runs: `tick`, which only changes the wallpaper
+    /// when a change is due, or `next` for raw expressions that `tick` can't
+    /// follow.
+    pub fn command(&self) -> &'static str {
+        match self {
+            Schedule::Raw { .. } => "next",
+            _ => "tick",
+        }
+    }
+
+    /// Minutes between the extra `tick` runs catching up on missed changes,
+    /// `None` when the schedule already runs that often, or for raw
+    /// expressions `tick` can't follow.
+    #[cfg(target_os = "linux")]
+    pub fn catch_up_minutes(&self) -> Option<u8> {
+        match self {
+            Schedule::Raw { .. } | Schedule::Minutes(1..=CATCH_UP_MINUTES) => None,
+            _ => Some(CATCH_UP_MINUTES),
+        }
+    }
+
+    /// Crontab lines running `action`.
+    ///
+    /// Cron can't count days across months, so every `n` days runs daily and
+    /// `tick` waits until `n` days have passed since the last change.
+    #[cfg(target_os = "linux")]
+    pub fn cron_entries(&self, action: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        let entries = match self {
+            Schedule::Minutes(n) => vec![format!("*/{} * * * * {}", n, action)],
+            Schedule::Hours(n) => vec![format!("0 */{} * * * {}", n, action)],
+            Schedule::Days { at, .. } => {
+                vec![format!("{} {} * * * {}", at.minute, at.hour, action)]
+            }
+            Schedule::Times { times, weekdays } => {
+                let weekdays = if weekdays.is_empty() {
+                    "*".to_string()
+                } else {
+                    weekdays
+                        .iter()
+                        .map(|weekday| &WEEKDAYS[*weekday][..3])
+                        .collect::<Vec<&str>>()
+                        .join(",")
+                };
+
+                times
+                    .iter()
+                    .map(|time| {
+                        format!("{} {} * * {} {}", time.minute, time.hour, weekdays, action)
+                    })
+                    .collect()
+            }
+            Schedule::Raw {
+                cron: Some(cron), ..
+            } => vec![format!("{} {}", cron.trim(), action)],
+            Schedule::Raw { cron: None, .. } => {
+                return Err(
+                    "Error: Only `on_calendar` is set, cron needs a `cron` expression.".into(),
+                )
+            }
+        };
+
+        Ok(entries)
+    }
+
+    /// `[Timer]` lines of a systemd timer.
+    ///
+    /// Intervals dividing the hour (or the day) become calendar events, which
+    /// run at the same times as the cron entries. Every `n` days runs daily,
+    /// like the cron entry. Short intervals that don't divide the hour count
+    /// from the last run, longer ones are left to the catch-up runs, which
+    /// would otherwise keep restarting that count.
+    #[cfg(target_os = "linux")]
+    pub fn systemd_timer(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        let daily = |at: &TimeOfDay| format!("OnCalendar=*-*-* {:02}:{:02}:00", at.hour, at.minute);
+
+        let mut timer = match self {
+            Schedule::Minutes(n) if 60 % n == 0 => vec![format!("OnCalendar=*:0/{}", n)],
+            Schedule::Minutes(n) if *n <= CATCH_UP_MINUTES => vec![
+                format!("OnActiveSec={}min", n),
+                format!("OnUnitActiveSec={}min", n),
+            ],
+            Schedule::Hours(n) if 24 % n == 0 => vec![format!("OnCalendar=0/{}:00", n)],
+            Schedule::Minutes(_) | Schedule::Hours(_) => Vec::new(),
+            Schedule::Days { at, .. } => vec![daily(at)],
+            Schedule::Times { times, weekdays } => {
+                let weekdays = if weekdays.is_empty() {
+                    String::new()
+                } else {
+                    let names: Vec<String> = weekdays
+                        .iter()
+                        .map(|weekday| {
+                            let name = &WEEKDAYS[*weekday][..3];
+                            name[..1].to_uppercase() + &name[1..]
+                        })
+                        .collect();
+                    format!("{} ", names.join(","))
+                };
+
+                times
+                    .iter()
+                    .map(|time| {
+                        format!(
+                            "OnCalendar={}*-*-* {:02}:{:02}:00",
+                            weekdays, time.hour, time.minute
+                        )
+                    })
+                    .collect()
+            }
+            Schedule::Raw {
+                on_calendar: Some(on_calendar),
+                ..
+            } => vec![format!("OnCalendar={}", on_calendar.trim())],
+            Schedule::Raw {
+                on_calendar: None, ..
+            } => {
+                return Err(
+                    "Error: Only `cron` is set, systemd needs an `on_calendar` expression.".into(),
+                )
+            }
+        };
+
+        // Calendar events missed during a suspend run once on resume, the
+        // catch-up runs make up for the rest.
+        if let Some(minutes) = self.catch_up_minutes() {
+            timer.push(format!("OnCalendar=*:0/{}", minutes));
+        }
+
+        Ok(timer)
+    }
+
+    /// `schtasks /create` arguments for the schedule (`/sc`, `/mo`, `/d`, `/st`).
+    ///
+    /// A task has a single start time, so lists of times and raw expressions
+    /// can't be scheduled.
+    #[cfg(target_os = "windows")]
+    pub fn schtasks_args(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        let at = |time: &TimeOfDay| format!("{:02}:{:02}", time.hour, time.minute);
+
+        let args = match self {
+            Schedule::Minutes(n) => {
+                vec!["/sc".into(), "MINUTE".into(), "/mo".into(), n.to_string()]
+            }
+            Schedule::Hours(n) => vec!["/sc".into(), "HOURLY".into(), "/mo".into(), n.to_string()],
+            // Like cron, every `n` days runs daily and leaves the count to `tick`.
+            Schedule::Days { at: time, .. } => {
+                vec!["/sc".into(), "DAILY".into(), "/st".into(), at(time)]
+            }
+            Schedule::Times { times, weekdays } if times.len() == 1 && weekdays.is_empty() => {
+                vec!["/sc".into(), "DAILY".into(), "/st".into(), at(&times[0])]
+            }
+            Schedule::Times { times, weekdays } if times.len() == 1 => {
+                let days: Vec<String> = weekdays
+                    .iter()
+                    .map(|weekday| WEEKDAYS[*weekday][..3].to_uppercase())
+                    .collect();
+                vec![
+                    "/sc".into(),
+                    "WEEKLY".into(),
+                    "/d".into(),
+                    days.join(","),
+                    "/st".into(),
+                    at(&times[0]),
+                ]
+            }
+            _ => return Err("Error: schtasks only takes a preset or a single time.".into()),
+        };
+
+        Ok(args)
+    }
+}
@@ -0,0 +1,178 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// src/schedulers/cron.rs
+
+use super::Scheduler;
+use crate::backends::find_in_path;
+use crate::core_functions::is_dry_run;
+use crate::core_models::DwTimeConfig;
+use crate::schedule::Schedule;
+use std::{
+    env,
+    error::Error,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// Lines delimiting our entries in the user's crontab. Everything between
+/// them belongs to us, everything else is left alone.
+const BEGIN: &str = "# BEGIN dwr";
+const END: &str = "# END dwr";
+
+/// Command of the entries added before the block existed, whatever their
+/// schedule was.
+const LEGACY_COMMAND: &str = "~/.dw/bin/dw next";
+
+/// `arg` as a single word for the shell cron runs entries with, with `%`
+/// escaped since cron turns it into a line break.
+fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''").replace('%', "\\%"))
+}
+
+/// The user's crontab, empty when there is none yet.
+fn read_crontab() -> Result<String, Box<dyn Error>> {
+    let output = Command::new("crontab")
+        .arg("-l")
+        .output()
+        .map_err(|e| format!("Error: Failed to run crontab: {}", e))?;
+
+    if output.status.success() {
+        return Ok(String::from_utf8_lossy(&output.stdout).to_string());
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.to_lowercase().contains("no crontab") {
+        return Ok(String::new());
+    }
+
+    Err(format!("Error: crontab -l failed: {}", stderr.trim()).into())
+}
+
+fn write_crontab(crontab: &str) -> Result<(), Box<dyn Error>> {
+    if is_dry_run() {
+        println!("[dry-run] would install the crontab:");
+        print!("{}", crontab);
+        return Ok(());
+    }
+
+    let mut child = Command::new("crontab")
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Error: Failed to run crontab: {}", e))?;
+    child
+        .stdin
+        .take()
+        .ok_or("Error: No crontab stdin")?
+        .write_all(crontab.as_bytes())?;
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(format!(
+            "Error: crontab - failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+/// `crontab` without our block, and with a new one holding `entries` at the
+/// end unless there are none.
+///
+/// Lines outside the block running [`LEGACY_COMMAND`] are dropped too.
+fn with_block(crontab: &str, entries: &[String]) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    let mut in_block = false;
+
+    for line in crontab.lines() {
+        match line.trim() {
+            BEGIN => in_block = true,
+            END => in_block = false,
+            trimmed if !in_block && !trimmed.ends_with(LEGACY_COMMAND) => lines.push(line),
+            _ => {}
+        }
+    }
+
+    let mut new_crontab: String = lines.iter().map(|line| format!("{}\n", line)).collect();
+    if !entries.is_empty() {
+        new_crontab.push_str(BEGIN);
+        new_crontab.push('\n');
+        for entry in entries {
+            new_crontab.push_str(entry);
+            new_crontab.push('\n');
+        }
+        new_crontab.push_str(END);
+        new_crontab.push('\n');
+    }
+
+    new_crontab
+}
+
+/// Makes `entries` the only lines of our block, creating it if needed.
+/// Installing the same entries again leaves the crontab untouched.
+fn install(entries: &[String]) -> Result<(), Box<dyn Error>> {
+    let crontab = read_crontab()?;
+    let new_crontab = with_block(&crontab, entries);
+
+    if new_crontab == crontab {
+        return Ok(());
+    }
+
+    write_crontab(&new_crontab)
+}
+
+/// Runs `dw tick` (or `dw next`) from a block of the user's crontab.
+pub struct CronScheduler;
+
+impl Scheduler for CronScheduler {
+    fn name(&self) -> &'static str {
+        "cron"
+    }
+
+    fn detect(&self) -> bool {
+        find_in_path("crontab").is_some()
+    }
+
+    fn enable(&self, time_config: &DwTimeConfig) -> Result<(), Box<dyn Error>> {
+        let exe = env::current_exe()?;
+        let exe = exe.to_str().ok_or("Invalid path")?;
+        if exe.contains('\n') {
+            return Err("Error: cron can't run a path with a line break.".into());
+        }
+
+        let schedule = Schedule::from_config(time_config)?;
+        let action = format!("{} {}", shell_quote(exe), schedule.command());
+        let mut entries = schedule.cron_entries(&action)?;
+        // Slots missed while the machine was off or asleep never run, so
+        // `tick` also runs every few minutes, only changing when one is due.
+        if let Some(minutes) = schedule.catch_up_minutes() {
+            entries.push(format!(
+                "*/{} * * * * {} tick > /dev/null",
+                minutes,
+                shell_quote(exe)
+            ));
+        }
+
+        install(&entries)
+    }
+
+    fn disable(&self) -> Result<(), Box<dyn Error>> {
+        install(&[])
+    }
+}
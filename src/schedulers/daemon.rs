@@ -0,0 +1,49 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// src/schedulers/daemon.rs
+
+use super::Scheduler;
+use crate::core_models::DwTimeConfig;
+use crate::daemon::running;
+use crate::schedule::Schedule;
+use chrono::Local;
+use std::error::Error;
+
+/// Leaves the changes to `dw daemon`, which follows the `enabled` flag of the
+/// config that `on` and `off` set, so there is nothing to install.
+pub struct DaemonScheduler;
+
+impl Scheduler for DaemonScheduler {
+    fn name(&self) -> &'static str {
+        "daemon"
+    }
+
+    fn detect(&self) -> bool {
+        running().is_some()
+    }
+
+    fn enable(&self, time_config: &DwTimeConfig) -> Result<(), Box<dyn Error>> {
+        Schedule::from_config(time_config)?
+            .next_after(Local::now())
+            .map(|_| ())
+            .ok_or_else(|| {
+                "Error: The daemon can't follow `cron` and `on_calendar` expressions.".into()
+            })
+    }
+
+    fn disable(&self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// src/schedulers/mod.rs
+
+mod cron;
+mod daemon;
+mod systemd;
+
+use crate::core_models::{DwSchedulerKind, DwTimeConfig};
+use cron::CronScheduler;
+use daemon::DaemonScheduler;
+use std::error::Error;
+use systemd::SystemdScheduler;
+
+/// A way of running `dw tick` periodically.
+pub trait Scheduler {
+    /// Short identifier, as in the `scheduler` config key (e.g. "cron").
+    fn name(&self) -> &'static str;
+
+    /// Returns `true` if this scheduler is available on this machine.
+    fn detect(&self) -> bool;
+
+    /// Schedules `dw tick` as set by `time_config`, replacing an earlier
+    /// schedule of ours.
+    fn enable(&self, time_config: &DwTimeConfig) -> Result<(), Box<dyn Error>>;
+
+    /// Removes our schedule, doing nothing when there is none.
+    fn disable(&self) -> Result<(), Box<dyn Error>>;
+}
+
+/// Every scheduler, by preference. A running daemon wins over the others.
+fn schedulers() -> Vec<Box<dyn Scheduler>> {
+    vec![
+        Box::new(DaemonScheduler),
+        Box::new(SystemdScheduler),
+        Box::new(CronScheduler),
+    ]
+}
+
+/// Schedules `dw tick` with `kind`, or with the first scheduler available, and
+/// removes the schedules of the others so changes don't run twice. Returns the
+/// name of the scheduler used.
+pub fn enable(
+    kind: Option<DwSchedulerKind>,
+    time_config: &DwTimeConfig,
+) -> Result<&'static str, Box<dyn Error>> {
+    let schedulers = schedulers();
+    let selected = match kind {
+        Some(kind) => schedulers
+            .iter()
+            .position(|scheduler| scheduler.name() == kind.as_str()),
+        None => schedulers.iter().position(|scheduler| scheduler.detect()),
+    }
+    .ok_or(
+        "Error: No scheduler found, install cron, run a systemd user session or start `dw daemon`.",
+    )?;
+
+    for (index, scheduler) in schedulers.iter().enumerate() {
+        // A scheduler that is installed but not working has nothing to remove.
+        if index != selected && scheduler.detect() {
+            let _ = scheduler.disable();
+        }
+    }
+
+    let scheduler = &schedulers[selected];
+    scheduler
+        .enable(time_config)
+        .map_err(|e| format!("{} scheduler: {}", scheduler.name(), e))?;
+
+    Ok(scheduler.name())
+}
+
+/// Removes the schedules of every available scheduler, but `except`'s.
+pub fn disable(except: Option<&str>) -> Result<(), Box<dyn Error>> {
+    for scheduler in schedulers()
+        .iter()
+        .filter(|scheduler| Some(scheduler.name()) != except && scheduler.detect())
+    {
+        scheduler
+            .disable()
+            .map_err(|e| format!("{} scheduler: {}", scheduler.name(), e))?;
+    }
+
+    Ok(())
+}
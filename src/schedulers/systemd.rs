@@ -0,0 +1,153 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// src/schedulers/systemd.rs
+
+use super::Scheduler;
+use crate::backends::{find_in_path, run};
+use crate::core_functions::is_dry_run;
+use crate::core_models::DwTimeConfig;
+use crate::schedule::Schedule;
+use std::{
+    env,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+const SERVICE: &str = "dwr-next.service";
+const TIMER: &str = "dwr-next.timer";
+
+/// Runs `dw tick` (or `dw next`) from a timer of the systemd user manager,
+/// which also hands it the environment of the graphical session.
+pub struct SystemdScheduler;
+
+impl SystemdScheduler {
+    /// `$XDG_CONFIG_HOME/systemd/user`, usually `~/.config/systemd/user`.
+    fn unit_dir() -> Result<PathBuf, Box<dyn Error>> {
+        let config_home = match env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+            Some(dir) => PathBuf::from(dir),
+            None => {
+                PathBuf::from(env::var_os("HOME").ok_or("Error: HOME is not set.")?).join(".config")
+            }
+        };
+
+        Ok(config_home.join("systemd/user"))
+    }
+
+    fn write_unit(path: &Path, contents: &str) -> Result<(), Box<dyn Error>> {
+        if is_dry_run() {
+            println!("[dry-run] would write {}:", path.display());
+            print!("{}", contents);
+            return Ok(());
+        }
+
+        fs::write(path, contents)
+            .map_err(|e| format!("Error: Failed to write {}: {}", path.display(), e).into())
+    }
+}
+
+/// Quotes an `ExecStart=` argument, escaping what systemd would expand.
+fn exec_quote(arg: &str) -> String {
+    let escaped = arg
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%")
+        .replace('$', "$$");
+
+    format!("\"{}\"", escaped)
+}
+
+impl Scheduler for SystemdScheduler {
+    fn name(&self) -> &'static str {
+        "systemd"
+    }
+
+    fn detect(&self) -> bool {
+        find_in_path("systemctl").is_some()
+            && env::var_os("XDG_RUNTIME_DIR")
+                .is_some_and(|dir| PathBuf::from(dir).join("systemd").is_dir())
+    }
+
+    fn enable(&self, time_config: &DwTimeConfig) -> Result<(), Box<dyn Error>> {
+        let exe = env::current_exe()?;
+        let exe = exe.to_str().ok_or("Invalid path")?;
+        if exe.contains('\n') {
+            return Err("Error: systemd can't run a path with a line break.".into());
+        }
+
+        let schedule = Schedule::from_config(time_config)?;
+        let command = schedule.command();
+        let timer_lines = schedule.systemd_timer()?;
+
+        let mut service = String::from(
+            "[Unit]\n\
+             Description=Switch to the next daily wallpaper\n\
+             \n\
+             [Service]\n\
+             Type=oneshot\n",
+        );
+        service.push_str(&format!("ExecStart={} {}\n", exec_quote(exe), command));
+
+        let timer = format!(
+            "[Unit]\n\
+             Description=Switch to the next daily wallpaper periodically\n\
+             \n\
+             [Timer]\n\
+             {}\n\
+             Persistent=true\n\
+             \n\
+             [Install]\n\
+             WantedBy=timers.target\n",
+            timer_lines.join("\n")
+        );
+
+        let unit_dir = SystemdScheduler::unit_dir()?;
+        if !is_dry_run() {
+            fs::create_dir_all(&unit_dir)
+                .map_err(|e| format!("Error: Failed to create {}: {}", unit_dir.display(), e))?;
+        }
+        SystemdScheduler::write_unit(&unit_dir.join(SERVICE), &service)?;
+        SystemdScheduler::write_unit(&unit_dir.join(TIMER), &timer)?;
+
+        run("systemctl", &["--user", "daemon-reload"])?;
+        run("systemctl", &["--user", "enable", TIMER])?;
+        // Restarting also starts the timer over with a changed schedule.
+        run("systemctl", &["--user", "restart", TIMER])
+    }
+
+    fn disable(&self) -> Result<(), Box<dyn Error>> {
+        let unit_dir = SystemdScheduler::unit_dir()?;
+        if !unit_dir.join(TIMER).exists() {
+            return Ok(());
+        }
+
+        run("systemctl", &["--user", "disable", "--now", TIMER])?;
+
+        for unit in [TIMER, SERVICE] {
+            let path = unit_dir.join(unit);
+            if is_dry_run() {
+                println!("[dry-run] would remove {}", path.display());
+                continue;
+            }
+
+            if path.exists() {
+                fs::remove_file(&path)
+                    .map_err(|e| format!("Error: Failed to remove {}: {}", path.display(), e))?;
+            }
+        }
+
+        run("systemctl", &["--user", "daemon-reload"])
+    }
+}
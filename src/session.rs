@@ -0,0 +1,208 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// src/session.rs
+
+use std::{
+    collections::HashMap,
+    env, fs,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Variables a wallpaper backend may need to reach the user's graphical session.
+const SESSION_VARIABLES: [&str; 9] = [
+    "XDG_RUNTIME_DIR",
+    "DBUS_SESSION_BUS_ADDRESS",
+    "WAYLAND_DISPLAY",
+    "DISPLAY",
+    "XAUTHORITY",
+    "XDG_CURRENT_DESKTOP",
+    "DESKTOP_SESSION",
+    "SWAYSOCK",
+    "HYPRLAND_INSTANCE_SIGNATURE",
+];
+
+/// Processes whose environment is known to describe the graphical session.
+const SESSION_PROCESSES: [&str; 13] = [
+    "gnome-shell",
+    "gnome-session-b",
+    "plasmashell",
+    "kwin_wayland",
+    "kwin_x11",
+    "xfce4-session",
+    "cinnamon",
+    "mate-session",
+    "budgie-wm",
+    "lxqt-session",
+    "sway",
+    "Hyprland",
+    "Xwayland",
+];
+
+/// Fills in the session variables missing from our own environment.
+///
+/// When started from cron or another detached scheduler the process has none of
+/// the variables a desktop needs, so they are looked up (in this order) in
+/// `systemctl --user show-environment`, in the environment of the user's session
+/// processes and finally guessed from the sockets in the runtime directories.
+/// Variables that are already set are never overwritten.
+///
+/// Setting `DWR_NO_SESSION_IMPORT` skips all of this, as documented in the
+/// README, so a container or a test setup never reaches the real desktop.
+pub fn import_session_environment() {
+    if env::var_os("DWR_NO_SESSION_IMPORT").is_some() {
+        return;
+    }
+
+    let uid = match fs::metadata("/proc/self") {
+        Ok(metadata) => metadata.uid(),
+        Err(_) => return,
+    };
+
+    let mut sources = vec![systemd_user_environment(uid), session_process_environment(uid)];
+    sources.push(guessed_environment(uid, &sources));
+
+    for variable in SESSION_VARIABLES {
+        if env::var_os(variable).is_some() {
+            continue;
+        }
+
+        if let Some(value) = sources.iter().find_map(|source| source.get(variable)) {
+            env::set_var(variable, value);
+        }
+    }
+}
+
+fn parse_environment<'a>(entries: impl Iterator<Item = &'a str>) -> HashMap<String, String> {
+    entries
+        .filter_map(|entry| entry.split_once('='))
+        .filter(|(key, _)| SESSION_VARIABLES.contains(key))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+/// `systemctl --user` reaches the user manager through XDG_RUNTIME_DIR, which
+/// is just what a detached process lacks, so the usual one is passed along.
+fn systemd_user_environment(uid: u32) -> HashMap<String, String> {
+    let mut command = Command::new("systemctl");
+    command.args(["--user", "show-environment"]);
+    if env::var_os("XDG_RUNTIME_DIR").is_none() {
+        command.env("XDG_RUNTIME_DIR", format!("/run/user/{}", uid));
+    }
+
+    match command.output() {
+        Ok(output) if output.status.success() => {
+            parse_environment(String::from_utf8_lossy(&output.stdout).lines())
+        }
+        _ => HashMap::new(),
+    }
+}
+
+/// Environment of a running process owned by `uid` that is attached to a display,
+/// preferring the well-known session processes over arbitrary ones.
+fn session_process_environment(uid: u32) -> HashMap<String, String> {
+    let mut candidates: Vec<(bool, HashMap<String, String>)> = Vec::new();
+
+    let entries = match fs::read_dir("/proc") {
+        Ok(entries) => entries,
+        Err(_) => return HashMap::new(),
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let proc_dir = entry.path();
+        let is_pid = entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| name.chars().all(|c| c.is_ascii_digit()));
+
+        if !is_pid || fs::metadata(&proc_dir).map(|m| m.uid()).ok() != Some(uid) {
+            continue;
+        }
+
+        let environ = match fs::read(proc_dir.join("environ")) {
+            Ok(environ) => environ,
+            Err(_) => continue,
+        };
+        let environ = String::from_utf8_lossy(&environ);
+        let variables = parse_environment(environ.split('\0'));
+
+        if !variables.contains_key("DISPLAY") && !variables.contains_key("WAYLAND_DISPLAY") {
+            continue;
+        }
+
+        let comm = fs::read_to_string(proc_dir.join("comm")).unwrap_or_default();
+        candidates.push((SESSION_PROCESSES.contains(&comm.trim()), variables));
+    }
+
+    candidates
+        .into_iter()
+        .max_by_key(|(known, variables)| (*known, variables.len()))
+        .map(|(_, variables)| variables)
+        .unwrap_or_default()
+}
+
+/// Last resort: derive the variables from the sockets the session leaves behind.
+fn guessed_environment(uid: u32, sources: &[HashMap<String, String>]) -> HashMap<String, String> {
+    let mut guessed = HashMap::new();
+
+    let runtime_dir = env::var("XDG_RUNTIME_DIR")
+        .ok()
+        .or_else(|| {
+            sources
+                .iter()
+                .find_map(|source| source.get("XDG_RUNTIME_DIR").cloned())
+        })
+        .unwrap_or_else(|| format!("/run/user/{}", uid));
+    let runtime_dir = PathBuf::from(runtime_dir);
+
+    if runtime_dir.is_dir() {
+        guessed.insert(
+            "XDG_RUNTIME_DIR".to_string(),
+            runtime_dir.to_string_lossy().to_string(),
+        );
+
+        if runtime_dir.join("bus").exists() {
+            guessed.insert(
+                "DBUS_SESSION_BUS_ADDRESS".to_string(),
+                format!("unix:path={}", runtime_dir.join("bus").display()),
+            );
+        }
+
+        if let Some(socket) = first_entry(&runtime_dir, |name| {
+            name.starts_with("wayland-") && !name.ends_with(".lock")
+        }) {
+            guessed.insert("WAYLAND_DISPLAY".to_string(), socket);
+        }
+    }
+
+    if let Some(socket) = first_entry(Path::new("/tmp/.X11-unix"), |name| name.starts_with('X')) {
+        guessed.insert("DISPLAY".to_string(), format!(":{}", &socket[1..]));
+    }
+
+    guessed
+}
+
+fn first_entry(dir: &Path, matches: impl Fn(&str) -> bool) -> Option<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| matches(name))
+        .collect();
+
+    names.sort();
+    names.into_iter().next()
+}
@@ -0,0 +1,240 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// src/span.rs
+
+use crate::backends::{find_in_path, query, run};
+use crate::core_functions::is_dry_run;
+use crate::core_models::DwOutput;
+use std::{
+    collections::hash_map::DefaultHasher,
+    env,
+    error::Error,
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+/// Where the slices of spanned images are kept between runs.
+const SPAN_CACHE_DIR: &str = "state/span";
+
+/// An output name and the image cut for it.
+pub type Slice = (String, PathBuf);
+
+/// Area of an output in the spanned picture, in layout coordinates.
+struct Placement {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+/// Places every output in the layout, moving it right (down) by `bezel` for
+/// each column (row) of outputs before it so the picture continues behind the
+/// monitor frames. The layout starts at 0,0.
+fn placements(outputs: &[DwOutput], bezel: u32) -> Vec<Placement> {
+    let logical = |output: &DwOutput| {
+        (
+            output.width as f64 / output.scale,
+            output.height as f64 / output.scale,
+        )
+    };
+
+    let mut right_edges: Vec<f64> = Vec::new();
+    let mut bottom_edges: Vec<f64> = Vec::new();
+    for output in outputs {
+        let (width, height) = logical(output);
+        right_edges.push(output.x as f64 + width);
+        bottom_edges.push(output.y as f64 + height);
+    }
+    right_edges.sort_by(f64::total_cmp);
+    right_edges.dedup();
+    bottom_edges.sort_by(f64::total_cmp);
+    bottom_edges.dedup();
+
+    let min_x = outputs.iter().map(|output| output.x).min().unwrap_or(0) as f64;
+    let min_y = outputs.iter().map(|output| output.y).min().unwrap_or(0) as f64;
+
+    outputs
+        .iter()
+        .map(|output| {
+            let (width, height) = logical(output);
+            let columns = right_edges
+                .iter()
+                .filter(|edge| **edge <= output.x as f64)
+                .count();
+            let rows = bottom_edges
+                .iter()
+                .filter(|edge| **edge <= output.y as f64)
+                .count();
+
+            Placement {
+                x: output.x as f64 - min_x + (columns as u32 * bezel) as f64,
+                y: output.y as f64 - min_y + (rows as u32 * bezel) as f64,
+                width,
+                height,
+            }
+        })
+        .collect()
+}
+
+/// ImageMagick `tool`, through the `magick` launcher of version 7 when present.
+fn magick(tool: &str) -> Result<(&'static str, Vec<&str>), Box<dyn Error>> {
+    if find_in_path("magick").is_some() {
+        let args = if tool == "convert" {
+            vec![]
+        } else {
+            vec![tool]
+        };
+        return Ok(("magick", args));
+    }
+
+    match tool {
+        "identify" if find_in_path("identify").is_some() => Ok(("identify", vec![])),
+        "convert" if find_in_path("convert").is_some() => Ok(("convert", vec![])),
+        _ => Err("Error: Spanning images needs ImageMagick (magick or convert).".into()),
+    }
+}
+
+fn image_size(image: &str) -> Result<(f64, f64), Box<dyn Error>> {
+    let (program, mut args) = magick("identify")?;
+    args.extend_from_slice(&["-format", "%w %h\n", image]);
+
+    let size = query(program, &args)?;
+    let (width, height) = size
+        .lines()
+        .next()
+        .and_then(|line| line.split_once(' '))
+        .ok_or_else(|| format!("Error: Unexpected image size {:?}", size))?;
+
+    Ok((width.parse()?, height.parse()?))
+}
+
+/// Removes the slices cached under any key other than `key`, that is of other
+/// images or layouts, so the cache holds a single image at a time.
+fn prune_cache(cache_dir: &Path, key: u64) -> Result<(), Box<dyn Error>> {
+    let prefix = format!("{:016x}-", key);
+
+    for entry in fs::read_dir(cache_dir)? {
+        let path = entry?.path();
+        let stale = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| !name.starts_with(&prefix));
+
+        if stale {
+            fs::remove_file(&path)
+                .map_err(|e| format!("Error: Failed to remove {}: {}", path.display(), e))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Slices of `image` for each of `outputs`, or `None` when the image isn't a
+/// panoramic one, that is when its aspect ratio is closer to a single output's
+/// than to the whole layout's.
+///
+/// The image is scaled to cover the layout, centered, and cut along the
+/// outputs' geometry. The slices of the last spanned image are cached, so
+/// setting it again only costs the lookup.
+pub fn span_slices(
+    image: &Path,
+    outputs: &[DwOutput],
+    bezel: u32,
+) -> Result<Option<Vec<Slice>>, Box<dyn Error>> {
+    if outputs.len() < 2 {
+        return Ok(None);
+    }
+
+    let image_as_str = image.to_str().ok_or("Invalid path")?;
+    let placements = placements(outputs, bezel);
+    let layout_width = placements
+        .iter()
+        .map(|placement| placement.x + placement.width)
+        .fold(0.0, f64::max);
+    let layout_height = placements
+        .iter()
+        .map(|placement| placement.y + placement.height)
+        .fold(0.0, f64::max);
+
+    let (image_width, image_height) = image_size(image_as_str)?;
+    let aspect_distance =
+        |width: f64, height: f64| ((image_width / image_height) / (width / height)).ln().abs();
+    if aspect_distance(layout_width, layout_height)
+        >= aspect_distance(placements[0].width, placements[0].height)
+    {
+        return Ok(None);
+    }
+
+    // Same image, same file and same layout give the same slices.
+    let mut hasher = DefaultHasher::new();
+    image.hash(&mut hasher);
+    if let Ok(metadata) = fs::metadata(image) {
+        metadata.len().hash(&mut hasher);
+        metadata.modified().ok().hash(&mut hasher);
+    }
+    for output in outputs {
+        output.name.hash(&mut hasher);
+        (output.width, output.height, output.x, output.y).hash(&mut hasher);
+        output.scale.to_bits().hash(&mut hasher);
+    }
+    bezel.hash(&mut hasher);
+    let key = hasher.finish();
+
+    // Backends get the slices by absolute path, they don't share our directory.
+    let cache_dir = env::current_dir()?.join(SPAN_CACHE_DIR);
+    if !is_dry_run() {
+        fs::create_dir_all(&cache_dir)
+            .map_err(|e| format!("Error: Failed to create {}: {}", cache_dir.display(), e))?;
+        prune_cache(&cache_dir, key)?;
+    }
+
+    let scale = f64::max(layout_width / image_width, layout_height / image_height);
+    let offset_x = (image_width * scale - layout_width) / 2.0;
+    let offset_y = (image_height * scale - layout_height) / 2.0;
+
+    let mut slices = Vec::new();
+    for (output, placement) in outputs.iter().zip(&placements) {
+        let slice = cache_dir.join(format!("{:016x}-{}.png", key, output.name));
+
+        if !slice.exists() {
+            let crop = format!(
+                "{}x{}+{}+{}",
+                (placement.width / scale).round(),
+                (placement.height / scale).round(),
+                ((offset_x + placement.x) / scale).round(),
+                ((offset_y + placement.y) / scale).round()
+            );
+            let resize = format!("{}x{}!", output.width, output.height);
+            let slice_as_str = slice.to_str().ok_or("Invalid path")?;
+
+            let (program, mut args) = magick("convert")?;
+            args.extend_from_slice(&[
+                image_as_str,
+                "-crop",
+                &crop,
+                "+repage",
+                "-resize",
+                &resize,
+                slice_as_str,
+            ]);
+            run(program, &args)?;
+        }
+
+        slices.push((output.name.clone(), slice));
+    }
+
+    Ok(Some(slices))
+}
@@ -0,0 +1,114 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// src/workspaces.rs
+
+use serde_json::Value;
+use std::{
+    env,
+    error::Error,
+    process::{Command, Stdio},
+};
+
+/// A workspace and the output it is shown on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Workspace {
+    pub name: String,
+    pub output: String,
+}
+
+/// The IPC client of the running sway or i3 session. Both speak the same
+/// protocol, so only the program differs.
+pub fn ipc_client() -> Option<&'static str> {
+    if env::var_os("SWAYSOCK").is_some() {
+        Some("swaymsg")
+    } else if env::var_os("I3SOCK").is_some() {
+        Some("i3-msg")
+    } else {
+        None
+    }
+}
+
+fn workspace(node: &Value) -> Option<Workspace> {
+    Some(Workspace {
+        name: node["name"].as_str()?.to_string(),
+        output: node["output"].as_str()?.to_string(),
+    })
+}
+
+/// The focused workspace, or `None` outside of sway and i3.
+pub fn focused_workspace() -> Result<Option<Workspace>, Box<dyn Error>> {
+    let client = match ipc_client() {
+        Some(client) => client,
+        None => return Ok(None),
+    };
+
+    let output = Command::new(client)
+        .args(["-t", "get_workspaces"])
+        .output()
+        .map_err(|e| format!("Error: Failed to run {}: {}", client, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Error: {} -t get_workspaces failed: {}",
+            client,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+
+    let workspaces: Vec<Value> = serde_json::from_slice(&output.stdout)?;
+
+    Ok(workspaces
+        .iter()
+        .find(|node| node["focused"].as_bool().unwrap_or(false))
+        .and_then(workspace))
+}
+
+/// Calls `on_focus` with every workspace that gets the focus, until the
+/// window manager goes away or `on_focus` fails.
+pub fn watch_workspaces<F>(mut on_focus: F) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(&Workspace) -> Result<(), Box<dyn Error>>,
+{
+    let client = ipc_client().ok_or("Error: Workspace events need a sway or i3 session.")?;
+
+    let mut child = Command::new(client)
+        .args(["-t", "subscribe", "-m", "[\"workspace\"]"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Error: Failed to run {}: {}", client, e))?;
+    let stdout = child.stdout.take().ok_or("Error: No event stream")?;
+
+    // swaymsg pretty-prints each event and i3-msg writes one per line, a
+    // streaming deserializer reads both.
+    for event in serde_json::Deserializer::from_reader(stdout).into_iter::<Value>() {
+        let event = event.map_err(|e| format!("Error: Invalid workspace event: {}", e))?;
+
+        if event["change"] != "focus" {
+            continue;
+        }
+
+        if let Some(focused) = workspace(&event["current"]) {
+            if let Err(e) = on_focus(&focused) {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(e);
+            }
+        }
+    }
+
+    let status = child.wait()?;
+    Err(format!("Error: The {} event stream ended ({}).", client, status).into())
+}
@@ -0,0 +1,86 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// tests/backend_selection.rs
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::Sandbox;
+
+fn selected(sandbox: &Sandbox, args: &[&str], vars: &[(&str, &str)]) -> String {
+    let output = sandbox.dw(args, vars);
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+
+    stdout
+        .lines()
+        .find_map(|line| line.strip_prefix("Selected: "))
+        .unwrap_or_else(|| panic!("no selection in {:?}", output))
+        .to_string()
+}
+
+#[test]
+fn desktop_lists_and_aliases_are_matched() {
+    let sandbox = Sandbox::new("backends-aliases");
+
+    for (vars, backend) in [
+        (vec![("XDG_CURRENT_DESKTOP", "ubuntu:GNOME")], "gnome"),
+        (vec![("XDG_CURRENT_DESKTOP", "X-Cinnamon")], "cinnamon"),
+        (vec![("XDG_CURRENT_DESKTOP", "Budgie:GNOME")], "budgie"),
+        (vec![("XDG_CURRENT_DESKTOP", "XFCE")], "xfce"),
+        (vec![("DESKTOP_SESSION", "plasmawayland")], "kde"),
+        (
+            vec![
+                ("XDG_CURRENT_DESKTOP", "KDE"),
+                ("DESKTOP_SESSION", "gnome-xorg"),
+            ],
+            "kde",
+        ),
+    ] {
+        let selection = selected(&sandbox, &["backends"], &vars);
+        assert!(
+            selection.starts_with(&format!("{} (", backend)),
+            "{:?} selected {}",
+            vars,
+            selection
+        );
+    }
+}
+
+#[test]
+fn forced_backend_skips_detection() {
+    let sandbox = Sandbox::new("backends-forced");
+    sandbox.recorder("feh");
+    let image = sandbox.image("pic.png");
+
+    let output = sandbox.dw(
+        &["--backend", "x11", "set-wallpaper", image.to_str().unwrap()],
+        &[("XDG_CURRENT_DESKTOP", "GNOME")],
+    );
+
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(sandbox.calls()[0][0], "feh");
+    assert!(selected(&sandbox, &["--backend", "x11", "backends"], &[]).starts_with("x11 (forced"));
+}
+
+#[test]
+fn unknown_forced_backend_is_an_error() {
+    let sandbox = Sandbox::new("backends-unknown");
+
+    let output = sandbox.dw(&["--backend", "nope", "backends"], &[]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unknown backend"));
+}
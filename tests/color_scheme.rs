@@ -0,0 +1,165 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// tests/color_scheme.rs
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::{sway_quoted, Sandbox};
+use std::path::Path;
+
+const SWAY: [(&str, &str); 1] = [("XDG_CURRENT_DESKTOP", "sway")];
+
+/// A settings portal answering with `color_scheme` (1 dark, 2 light), and
+/// announcing a change to it when monitored.
+fn portal(sandbox: &Sandbox, color_scheme: u32) {
+    sandbox.program(
+        "gdbus",
+        &format!(
+            "if [ \"$1\" = monitor ]; then
+                echo \"/org/freedesktop/portal/desktop: org.freedesktop.portal.Settings.SettingChanged ('org.freedesktop.appearance', 'color-scheme', <uint32 {0}>)\"
+            else
+                echo '(<<uint32 {0}>>,)'
+            fi",
+            color_scheme
+        ),
+    );
+}
+
+fn write_config(sandbox: &Sandbox, actual: &Path, candidate: &Path, extra: &str) {
+    sandbox.write(
+        "home/.dwr/config/config.json",
+        &format!(
+            r#"{{
+                "actual_wallpaper": {{
+                    "index": 0,
+                    "path": {:?},
+                    "date_set": "2024-07-01T00:00:00-03:00",
+                    "child": {},
+                    "sub_index": 0
+                }},
+                "time_config": {{ "preset": "DAY", "interval": 1 }},
+                "candidates": [{:?}]{}
+            }}"#,
+            actual.display().to_string(),
+            candidate.is_dir(),
+            candidate.display().to_string(),
+            extra
+        ),
+    );
+}
+
+fn swaymsg_image(call: &[String]) -> &str {
+    assert_eq!(call[..4], ["swaymsg", "output", "*", "bg"]);
+    &call[4]
+}
+
+#[test]
+fn dark_sibling_is_used_in_dark_mode_only() {
+    let sandbox = Sandbox::new("scheme-sibling");
+    let light = sandbox.image("hills.png");
+    let dark = sandbox.image("hills-dark.png");
+    sandbox.recorder("swaymsg");
+
+    for (call, (color_scheme, expected)) in [(1, &dark), (2, &light)].into_iter().enumerate() {
+        portal(&sandbox, color_scheme);
+
+        let output = sandbox.dw(&["set-wallpaper", light.to_str().unwrap()], &SWAY);
+        assert!(output.status.success(), "{:?}", output);
+        assert_eq!(
+            swaymsg_image(&sandbox.calls()[call]),
+            sway_quoted(expected.to_str().unwrap())
+        );
+    }
+}
+
+#[test]
+fn dark_variants_are_not_cycled_on_their_own() {
+    let sandbox = Sandbox::new("scheme-directory");
+    let first = sandbox.image("a.png");
+    let first_dark = sandbox.image("a_dark.png");
+    let second = sandbox.image("b.png");
+    write_config(&sandbox, &first, first.parent().unwrap(), "");
+    sandbox.recorder("swaymsg");
+    portal(&sandbox, 1);
+
+    for _ in 0..2 {
+        let output = sandbox.dw(&["next"], &SWAY);
+        assert!(output.status.success(), "{:?}", output);
+    }
+
+    let calls = sandbox.calls();
+    assert_eq!(
+        swaymsg_image(&calls[0]),
+        sway_quoted(second.to_str().unwrap())
+    );
+    assert_eq!(
+        swaymsg_image(&calls[1]),
+        sway_quoted(first_dark.to_str().unwrap())
+    );
+}
+
+#[test]
+fn gnome_gets_both_variants_declared_in_the_config() {
+    let sandbox = Sandbox::new("scheme-gnome");
+    let light = sandbox.image("day.png");
+    let dark = sandbox.image("night.png");
+    write_config(
+        &sandbox,
+        &light,
+        &light,
+        &format!(
+            r#", "dark_variants": {{ {:?}: {:?} }}"#,
+            light.display().to_string(),
+            dark.display().to_string()
+        ),
+    );
+    sandbox.recorder("gsettings");
+
+    let output = sandbox.dw(
+        &["set-wallpaper", light.to_str().unwrap()],
+        &[("XDG_CURRENT_DESKTOP", "GNOME")],
+    );
+
+    assert!(output.status.success(), "{:?}", output);
+    let calls = sandbox.calls();
+    assert_eq!(calls[0][3], "picture-uri");
+    assert_eq!(calls[0][4], format!("file://{}", light.display()));
+    assert_eq!(calls[1][3], "picture-uri-dark");
+    assert_eq!(calls[1][4], format!("file://{}", dark.display()));
+}
+
+#[test]
+fn watcher_applies_the_wallpaper_again_when_the_scheme_flips() {
+    let sandbox = Sandbox::new("scheme-watch");
+    let light = sandbox.image("hills.png");
+    let dark = sandbox.image("hills-dark.png");
+    write_config(&sandbox, &light, &light, "");
+    sandbox.recorder("swaymsg");
+    portal(&sandbox, 1);
+
+    let output = sandbox.dw(&["watch-color-scheme"], &SWAY);
+
+    // The fake portal stops after one signal, which ends the watcher.
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("(exit code 47)"));
+    let calls = sandbox.calls();
+    assert_eq!(calls.len(), 1, "{:?}", calls);
+    assert_eq!(
+        swaymsg_image(&calls[0]),
+        sway_quoted(dark.to_str().unwrap())
+    );
+}
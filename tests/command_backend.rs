@@ -0,0 +1,111 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// tests/command_backend.rs
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::Sandbox;
+
+fn write_config(sandbox: &Sandbox, backend: &str) {
+    sandbox.write(
+        "home/.dwr/config/config.json",
+        &format!(
+            r#"{{
+                "actual_wallpaper": {{
+                    "index": 0,
+                    "path": "",
+                    "date_set": "2024-07-01T00:00:00-03:00",
+                    "child": false,
+                    "sub_index": 0
+                }},
+                "time_config": {{ "preset": "DAY", "interval": 1 }},
+                "candidates": [],
+                "display": {{ "mode": "fit", "output": "DP-1" }},
+                "backend": {}
+            }}"#,
+            backend
+        ),
+    );
+}
+
+#[test]
+fn template_placeholders_are_expanded_per_argument() {
+    let sandbox = Sandbox::new("command-template");
+    sandbox.recorder("my-setter");
+    write_config(
+        &sandbox,
+        r#"{ "command": ["my-setter", "--image={path}", "{uri}", "{output}", "{mode}"] }"#,
+    );
+    let image = sandbox.image("{mode} pic.png");
+
+    let output = sandbox.dw(
+        &["set-wallpaper", image.to_str().unwrap()],
+        &[("XDG_CURRENT_DESKTOP", "GNOME")],
+    );
+
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        sandbox.calls(),
+        vec![vec![
+            "my-setter".to_string(),
+            format!("--image={}", image.display()),
+            format!(
+                "file://{}",
+                image
+                    .display()
+                    .to_string()
+                    .replace("{mode} pic", "%7Bmode%7D%20pic")
+            ),
+            "DP-1".to_string(),
+            "fit".to_string(),
+        ]]
+    );
+}
+
+#[test]
+fn failing_template_command_is_reported() {
+    let sandbox = Sandbox::new("command-failure");
+    sandbox.program("my-setter", "exit 3");
+    write_config(&sandbox, r#"{ "command": ["my-setter", "{path}"] }"#);
+    let image = sandbox.image("a.png");
+
+    let output = sandbox.dw(&["set-wallpaper", image.to_str().unwrap()], &[]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("command backend"));
+}
+
+#[test]
+fn empty_template_is_refused_with_the_config() {
+    let sandbox = Sandbox::new("command-empty");
+    write_config(&sandbox, r#"{ "command": [] }"#);
+    let image = sandbox.image("a.png");
+
+    for args in [
+        vec!["set-wallpaper", image.to_str().unwrap()],
+        vec!["--backend", "command", "set-wallpaper", image.to_str().unwrap()],
+    ] {
+        let output = sandbox.dw(&args, &[("XDG_CURRENT_DESKTOP", "GNOME")]);
+
+        assert_eq!(output.status.code(), Some(1), "{:?}", output);
+        assert!(
+            String::from_utf8_lossy(&output.stderr).contains("backend command"),
+            "{:?}",
+            output
+        );
+    }
+}
@@ -0,0 +1,151 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// tests/common/mod.rs
+
+#![allow(dead_code)]
+
+use std::{
+    env, fs,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    process::{Child, Command, Output, Stdio},
+};
+
+/// A 1x1 PNG, enough for `tree_magic` to detect an image.
+const PNG: [u8; 69] = [
+    0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52,
+    0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x02, 0x00, 0x00, 0x00, 0x90, 0x77, 0x53,
+    0xde, 0x00, 0x00, 0x00, 0x0c, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0xf8, 0xcf, 0xc0, 0x00,
+    0x00, 0x03, 0x01, 0x01, 0x00, 0xc9, 0xfe, 0x92, 0xef, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e,
+    0x44, 0xae, 0x42, 0x60, 0x82,
+];
+
+/// `arg` quoted as a single word of a sway command.
+pub fn sway_quoted(arg: &str) -> String {
+    format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// `dw` the way cron entries run it.
+pub fn cron_dw() -> String {
+    format!("'{}'", env!("CARGO_BIN_EXE_daily-wallpapers-rust"))
+}
+
+/// An isolated HOME with its own `~/.dwr` and a `bin` directory that is the only
+/// thing on PATH besides the system directories, so fake setters can be planted.
+pub struct Sandbox {
+    pub root: PathBuf,
+}
+
+impl Sandbox {
+    pub fn new(name: &str) -> Sandbox {
+        let root = env::temp_dir().join(format!("dwr-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("home/.dwr/config")).unwrap();
+        fs::create_dir_all(root.join("bin")).unwrap();
+        fs::create_dir_all(root.join("images")).unwrap();
+        Sandbox { root }
+    }
+
+    pub fn home(&self) -> PathBuf {
+        self.root.join("home")
+    }
+
+    pub fn log(&self) -> PathBuf {
+        self.root.join("calls.log")
+    }
+
+    /// Writes a PNG called `name` in the images directory and returns its path.
+    pub fn image(&self, name: &str) -> PathBuf {
+        let path = self.root.join("images").join(name);
+        fs::write(&path, PNG).unwrap();
+        path
+    }
+
+    /// Plants an executable `name` in the sandbox PATH running `script`.
+    pub fn program(&self, name: &str, script: &str) {
+        let path = self.root.join("bin").join(name);
+        fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    /// Plants a program that records its name and arguments, one per line, in the log.
+    /// `printf` keeps backslashes that `echo` would interpret.
+    pub fn recorder(&self, name: &str) {
+        self.program(
+            name,
+            &format!(
+                "{{ echo \"$(basename \"$0\")\"; for arg in \"$@\"; do printf '%s\\n' \"$arg\"; done; echo --; }} >> '{}'",
+                self.log().display()
+            ),
+        );
+    }
+
+    /// Every recorded invocation as its argv.
+    pub fn calls(&self) -> Vec<Vec<String>> {
+        let log = fs::read_to_string(self.log()).unwrap_or_default();
+        log.split("--\n")
+            .filter(|call| !call.is_empty())
+            .map(|call| call.lines().map(String::from).collect())
+            .collect()
+    }
+
+    pub fn write(&self, relative: &str, contents: &str) {
+        let path = self.root.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    /// `dw` with `args` and only the given variables in its environment, with
+    /// session discovery turned off.
+    fn command(&self, args: &[&str], vars: &[(&str, &str)]) -> Command {
+        let path = format!("{}:/usr/bin:/bin", self.root.join("bin").display());
+
+        let mut command = Command::new(env!("CARGO_BIN_EXE_daily-wallpapers-rust"));
+        command
+            .args(args)
+            .env_clear()
+            .env("HOME", self.home())
+            .env("PATH", path)
+            .env("XDG_RUNTIME_DIR", self.root.join("run"))
+            // The real session must never leak into a test, or its setters run.
+            .env("DWR_NO_SESSION_IMPORT", "1")
+            .envs(vars.iter().copied());
+        command
+    }
+
+    /// Runs `dw` with `args` and only the given variables in its environment.
+    pub fn dw(&self, args: &[&str], vars: &[(&str, &str)]) -> Output {
+        self.command(args, vars).output().unwrap()
+    }
+
+    /// Starts `dw` like `dw` does, without waiting for it.
+    pub fn spawn(&self, args: &[&str], vars: &[(&str, &str)]) -> Child {
+        self.command(args, vars)
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap()
+    }
+
+    pub fn exists(&self, relative: &str) -> bool {
+        Path::new(&self.root.join(relative)).exists()
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
@@ -0,0 +1,172 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// tests/crontab.rs
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::{cron_dw, Sandbox};
+use std::fs;
+
+/// A `crontab` keeping the table in the sandbox, failing like cronie's when
+/// there is none.
+fn fake_crontab(sandbox: &Sandbox) {
+    let table = sandbox.root.join("crontab");
+    sandbox.program(
+        "crontab",
+        &format!(
+            "table='{}'
+            case \"$1\" in
+                -l) [ -f \"$table\" ] || {{ echo \"no crontab for $USER\" >&2; exit 1; }}; cat \"$table\" ;;
+                -) cat > \"$table\" ;;
+                *) exit 2 ;;
+            esac",
+            table.display()
+        ),
+    );
+}
+
+fn write_config(sandbox: &Sandbox, preset: &str, interval: u8) {
+    sandbox.write(
+        "home/.dwr/config/config.json",
+        &format!(
+            r#"{{
+                "actual_wallpaper": {{
+                    "index": 0,
+                    "path": "",
+                    "date_set": "2024-07-01T00:00:00-03:00",
+                    "child": false,
+                    "sub_index": 0
+                }},
+                "time_config": {{ "preset": "{}", "interval": {} }},
+                "candidates": []
+            }}"#,
+            preset, interval
+        ),
+    );
+}
+
+fn crontab(sandbox: &Sandbox) -> String {
+    fs::read_to_string(sandbox.root.join("crontab")).unwrap()
+}
+
+#[test]
+fn on_is_idempotent_and_keeps_other_entries() {
+    let sandbox = Sandbox::new("crontab-on");
+    fake_crontab(&sandbox);
+    sandbox.write("crontab", "MAILTO=\"\"\n30 2 * * * backup --all /\n");
+    write_config(&sandbox, "HOUR", 2);
+
+    for _ in 0..2 {
+        let output = sandbox.dw(&["on"], &[]);
+        assert!(output.status.success(), "{:?}", output);
+    }
+
+    assert_eq!(
+        crontab(&sandbox),
+        format!(
+            "MAILTO=\"\"\n30 2 * * * backup --all /\n\
+             # BEGIN dwr\n0 */2 * * * {0} tick\n*/15 * * * * {0} tick > /dev/null\n# END dwr\n",
+            cron_dw()
+        )
+    );
+
+    // A new preset replaces the entries instead of adding some, and needs no
+    // catch-up entry since it runs as often.
+    write_config(&sandbox, "MINUTE", 15);
+    let output = sandbox.dw(&["on"], &[]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        crontab(&sandbox),
+        format!(
+            "MAILTO=\"\"\n30 2 * * * backup --all /\n\
+             # BEGIN dwr\n*/15 * * * * {} tick\n# END dwr\n",
+            cron_dw()
+        )
+    );
+}
+
+#[test]
+fn on_creates_the_crontab_and_off_removes_only_the_block() {
+    let sandbox = Sandbox::new("crontab-off");
+    fake_crontab(&sandbox);
+    write_config(&sandbox, "HOUR", 1);
+
+    let output = sandbox.dw(&["on"], &[]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        crontab(&sandbox),
+        format!(
+            "# BEGIN dwr\n0 */1 * * * {0} tick\n*/15 * * * * {0} tick > /dev/null\n# END dwr\n",
+            cron_dw()
+        )
+    );
+
+    let mut table = crontab(&sandbox);
+    table.push_str("@reboot ~/bin/sync-mail\n");
+    sandbox.write("crontab", &table);
+
+    let output = sandbox.dw(&["off"], &[]);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Agendamento removido"));
+    assert_eq!(crontab(&sandbox), "@reboot ~/bin/sync-mail\n");
+}
+
+#[test]
+fn dry_run_leaves_the_crontab_alone() {
+    let sandbox = Sandbox::new("crontab-dry-run");
+    fake_crontab(&sandbox);
+    write_config(&sandbox, "HOUR", 1);
+
+    let output = sandbox.dw(&["--dry-run", "on"], &[]);
+
+    assert!(output.status.success(), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("# BEGIN dwr"));
+    assert!(!sandbox.exists("crontab"));
+}
+
+#[test]
+fn entries_from_before_the_block_are_dropped() {
+    let legacy = "0 */2 * * * ~/.dw/bin/dw next\n0 */3 * * * ~/bin/other next\n";
+
+    // The old entry followed an older preset than the one now configured.
+    let sandbox = Sandbox::new("crontab-legacy");
+    fake_crontab(&sandbox);
+    sandbox.write("crontab", legacy);
+    write_config(&sandbox, "HOUR", 3);
+
+    let output = sandbox.dw(&["on"], &[]);
+
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        crontab(&sandbox),
+        format!(
+            "0 */3 * * * ~/bin/other next\n\
+             # BEGIN dwr\n0 */3 * * * {0} tick\n*/15 * * * * {0} tick > /dev/null\n# END dwr\n",
+            cron_dw()
+        )
+    );
+
+    let sandbox = Sandbox::new("crontab-legacy-off");
+    fake_crontab(&sandbox);
+    sandbox.write("crontab", legacy);
+    write_config(&sandbox, "HOUR", 3);
+
+    let output = sandbox.dw(&["off"], &[]);
+
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(crontab(&sandbox), "0 */3 * * * ~/bin/other next\n");
+}
@@ -0,0 +1,210 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// tests/daemon.rs
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::{cron_dw, Sandbox};
+use std::{
+    fs,
+    path::Path,
+    process::{Child, Command, Output},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// A config cycling through one image with a recording `setter`, and an empty
+/// crontab, which the daemon clears on start.
+fn daemon_sandbox(name: &str, enabled: bool, date_set: &str) -> Sandbox {
+    let sandbox = Sandbox::new(name);
+    sandbox.recorder("setter");
+    sandbox.write("crontab", "");
+    sandbox.program(
+        "crontab",
+        &format!(
+            "case \"$1\" in -l) cat '{0}' ;; -) cat > '{0}' ;; esac",
+            sandbox.root.join("crontab").display()
+        ),
+    );
+    let image = sandbox.image("a.png");
+    sandbox.write(
+        "home/.dwr/config/config.json",
+        &format!(
+            r#"{{
+                "actual_wallpaper": {{
+                    "index": 0,
+                    "path": "{0}",
+                    "date_set": "{1}",
+                    "child": false,
+                    "sub_index": 0
+                }},
+                "time_config": {{ "preset": "MINUTE", "interval": 5 }},
+                "enabled": {2},
+                "candidates": ["{0}"],
+                "backend": {{ "command": ["setter", "{{path}}"] }}
+            }}"#,
+            image.display(),
+            date_set,
+            enabled
+        ),
+    );
+    sandbox
+}
+
+fn wait_for(what: &str, condition: impl Fn() -> bool) {
+    let start = Instant::now();
+    while !condition() {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "timed out waiting for {}",
+            what
+        );
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+fn start_daemon(sandbox: &Sandbox) -> Child {
+    let child = sandbox.spawn(&["daemon"], &[]);
+    wait_for("the pidfile", || sandbox.exists("run/dwr-daemon.pid"));
+    child
+}
+
+fn terminate(child: Child) -> Output {
+    let status = Command::new("kill")
+        .args(["-TERM", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    child.wait_with_output().unwrap()
+}
+
+fn config(sandbox: &Sandbox) -> String {
+    fs::read_to_string(sandbox.root.join("home/.dwr/config/config.json")).unwrap()
+}
+
+#[test]
+fn changes_once_when_due_and_stops_on_sigterm() {
+    let sandbox = daemon_sandbox("daemon-due", true, "2024-07-01T00:00:00-03:00");
+
+    let child = start_daemon(&sandbox);
+    assert_eq!(
+        fs::read_to_string(sandbox.root.join("run/dwr-daemon.pid")).unwrap(),
+        format!("{}\n", child.id())
+    );
+    wait_for("the change", || !sandbox.calls().is_empty());
+    // The change saved a new date, so nothing is due for five minutes.
+    thread::sleep(Duration::from_secs(1));
+    let output = terminate(child);
+
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(sandbox.calls().len(), 1);
+    assert!(!config(&sandbox).contains("2024-07-01"));
+    assert!(!sandbox.exists("run/dwr-daemon.pid"));
+}
+
+#[test]
+fn on_and_off_toggle_a_running_daemon() {
+    let sandbox = daemon_sandbox("daemon-toggle", false, "2024-07-01T00:00:00-03:00");
+    sandbox.write(
+        "crontab",
+        &format!("# BEGIN dwr\n*/5 * * * * {} tick\n# END dwr\n", cron_dw()),
+    );
+
+    let child = start_daemon(&sandbox);
+    thread::sleep(Duration::from_secs(1));
+    assert!(sandbox.calls().is_empty());
+    // The daemon takes over from the cron entries of an earlier `on`.
+    assert_eq!(
+        fs::read_to_string(sandbox.root.join("crontab")).unwrap(),
+        ""
+    );
+
+    let output = sandbox.dw(&["on"], &[]);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("scheduled with daemon"));
+    assert!(config(&sandbox).contains("\"enabled\": true"));
+    assert_eq!(
+        fs::read_to_string(sandbox.root.join("crontab")).unwrap(),
+        ""
+    );
+    wait_for("the change", || !sandbox.calls().is_empty());
+
+    let output = sandbox.dw(&["off"], &[]);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(config(&sandbox).contains("\"enabled\": false"));
+
+    assert!(terminate(child).status.success());
+}
+
+#[test]
+fn a_config_without_candidates_is_reported_and_waited_out() {
+    let sandbox = daemon_sandbox("daemon-empty", true, "2024-07-01T00:00:00-03:00");
+    let image = sandbox.root.join("images/a.png");
+    let empty = config(&sandbox).replace(&format!("[\"{}\"]", image.display()), "[]");
+    sandbox.write("home/.dwr/config/config.json", &empty);
+
+    let mut child = start_daemon(&sandbox);
+    thread::sleep(Duration::from_secs(1));
+    assert!(child.try_wait().unwrap().is_none(), "the daemon died");
+    let output = terminate(child);
+
+    assert!(output.status.success(), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No candidates"));
+    assert!(sandbox.calls().is_empty());
+}
+
+#[test]
+fn a_second_daemon_is_refused() {
+    let sandbox = daemon_sandbox("daemon-twice", false, "2024-07-01T00:00:00-03:00");
+
+    let child = start_daemon(&sandbox);
+    let output = sandbox.dw(&["daemon"], &[]);
+    let first = terminate(child);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("already running"));
+    assert!(first.status.success(), "{:?}", first);
+}
+
+#[test]
+fn color_scheme_changes_are_followed_and_the_monitor_is_stopped() {
+    let sandbox = daemon_sandbox("daemon-scheme", false, "2024-07-01T00:00:00-03:00");
+    let dark = sandbox.image("a-dark.png");
+    // A dark portal announcing one change, then staying up like the real one.
+    sandbox.program(
+        "gdbus",
+        &format!(
+            "if [ \"$1\" = monitor ]; then
+                echo $$ > '{}'
+                echo \"/org/freedesktop/portal/desktop: org.freedesktop.portal.Settings.SettingChanged ('org.freedesktop.appearance', 'color-scheme', <uint32 1>)\"
+                exec sleep 60
+            else
+                echo '(<<uint32 1>>,)'
+            fi",
+            sandbox.root.join("gdbus.pid").display()
+        ),
+    );
+
+    let child = start_daemon(&sandbox);
+    wait_for("the change", || !sandbox.calls().is_empty());
+    let output = terminate(child);
+
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(sandbox.calls(), [["setter", dark.to_str().unwrap()]]);
+    let monitor = fs::read_to_string(sandbox.root.join("gdbus.pid")).unwrap();
+    assert!(!Path::new(&format!("/proc/{}", monitor.trim())).exists());
+}
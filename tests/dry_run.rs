@@ -0,0 +1,75 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// tests/dry_run.rs
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::Sandbox;
+use std::fs;
+
+#[test]
+fn next_reports_the_change_without_applying_it() {
+    let sandbox = Sandbox::new("dry-run-next");
+    let first = sandbox.image("1.png");
+    let second = sandbox.image("2.png");
+    let config = format!(
+        r#"{{
+  "actual_wallpaper": {{
+    "index": 0,
+    "path": "{}",
+    "date_set": "2024-07-01T00:00:00-03:00",
+    "child": true,
+    "sub_index": 0
+  }},
+  "time_config": {{ "preset": "DAY", "interval": 1 }},
+  "candidates": ["{}"]
+}}"#,
+        first.display(),
+        first.parent().unwrap().display()
+    );
+    sandbox.write("home/.dwr/config/config.json", &config);
+    sandbox.recorder("gsettings");
+
+    let output = sandbox.dw(&["--dry-run", "next"], &[("XDG_CURRENT_DESKTOP", "GNOME")]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{:?}", output);
+    assert!(stdout.contains(&format!("+     \"path\": \"{}\"", second.display())));
+    assert!(stdout.contains("[dry-run] would run: gsettings"));
+    assert!(sandbox.calls().is_empty());
+    assert_eq!(
+        fs::read_to_string(sandbox.home().join(".dwr/config/config.json")).unwrap(),
+        config
+    );
+}
+
+#[test]
+fn falls_back_to_recording_without_a_desktop() {
+    let sandbox = Sandbox::new("dry-run-headless");
+    let image = sandbox.image("a.png");
+
+    let output = sandbox.dw(
+        &["set-wallpaper", image.to_str().unwrap(), "--dry-run"],
+        &[],
+    );
+
+    assert!(output.status.success(), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stdout).contains(&format!(
+        "[dry-run] would set wallpaper: {}",
+        image.display()
+    )));
+}
@@ -0,0 +1,200 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// tests/hostile_filenames.rs
+//
+// Every backend must hand the wallpaper path to its setter as data: quotes,
+// command substitutions and shell metacharacters in a filename must neither
+// break the command nor be executed.
+
+mod common;
+
+use common::{sway_quoted, Sandbox};
+use std::path::Path;
+
+const HOSTILE_NAMES: [&str; 9] = [
+    "it's.png",
+    "$(touch pwned).png",
+    "`touch pwned`.png",
+    "\"quoted\".png",
+    "semi;colon & amp.png",
+    "back\\slash.png",
+    "spaces  and\ttab.png",
+    "x;exec touch pwned.png",
+    "comma, and more.png",
+];
+
+fn uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for byte in path.to_str().unwrap().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/// Sets every hostile filename through the backend selected by `vars` and
+/// returns, per filename, the image path and the calls the setters received.
+fn set_hostile_wallpapers(
+    sandbox: &Sandbox,
+    vars: &[(&str, &str)],
+) -> Vec<(std::path::PathBuf, Vec<Vec<String>>)> {
+    HOSTILE_NAMES
+        .iter()
+        .map(|name| {
+            let image = sandbox.image(name);
+            let _ = std::fs::remove_file(sandbox.log());
+
+            let output = sandbox.dw(&["set-wallpaper", image.to_str().unwrap()], vars);
+            assert!(
+                output.status.success(),
+                "{:?} failed: {}",
+                name,
+                String::from_utf8_lossy(&output.stderr)
+            );
+            assert!(
+                !sandbox.exists("home/.dwr/pwned"),
+                "{:?} ran a command",
+                name
+            );
+
+            (image, sandbox.calls())
+        })
+        .collect()
+}
+
+fn assert_passed_verbatim(calls: &[Vec<String>], program: &str, expected: &str) {
+    assert!(
+        calls
+            .iter()
+            .any(|call| call[0] == program && call.iter().any(|arg| arg == expected)),
+        "{} never received {:?}: {:?}",
+        program,
+        expected,
+        calls
+    );
+}
+
+#[test]
+fn gnome_receives_file_uris() {
+    let sandbox = Sandbox::new("hostile-gnome");
+    sandbox.recorder("gsettings");
+
+    for (image, calls) in set_hostile_wallpapers(&sandbox, &[("XDG_CURRENT_DESKTOP", "GNOME")]) {
+        assert_passed_verbatim(&calls, "gsettings", &uri(&image));
+    }
+}
+
+#[test]
+fn mate_receives_plain_paths() {
+    let sandbox = Sandbox::new("hostile-mate");
+    sandbox.recorder("gsettings");
+
+    for (image, calls) in set_hostile_wallpapers(&sandbox, &[("XDG_CURRENT_DESKTOP", "MATE")]) {
+        assert_passed_verbatim(&calls, "gsettings", image.to_str().unwrap());
+    }
+}
+
+#[test]
+fn xfce_receives_plain_paths() {
+    let sandbox = Sandbox::new("hostile-xfce");
+    sandbox.recorder("xfconf-query-recorder");
+    sandbox.program(
+        "xfconf-query",
+        "case \"$*\" in *--list*) echo /backdrop/screen0/monitorHDMI-1/workspace0/last-image ;; \
+         *) exec xfconf-query-recorder \"$@\" ;; esac",
+    );
+
+    for (image, calls) in set_hostile_wallpapers(&sandbox, &[("XDG_CURRENT_DESKTOP", "XFCE")]) {
+        assert_passed_verbatim(&calls, "xfconf-query-recorder", image.to_str().unwrap());
+    }
+}
+
+#[test]
+fn kde_script_embeds_an_escaped_string_literal() {
+    let sandbox = Sandbox::new("hostile-kde");
+    sandbox.recorder("qdbus");
+
+    for (image, calls) in set_hostile_wallpapers(&sandbox, &[("XDG_CURRENT_DESKTOP", "KDE")]) {
+        let literal = format!("\"{}\"", uri(&image));
+        assert!(
+            calls.iter().flatten().any(|arg| arg.contains(&literal)),
+            "script does not embed {}: {:?}",
+            literal,
+            calls
+        );
+    }
+}
+
+#[test]
+fn sway_command_quotes_the_path() {
+    let sandbox = Sandbox::new("hostile-sway");
+    sandbox.recorder("swaymsg");
+
+    // swaymsg joins its arguments into the command string sway parses.
+    for (image, calls) in set_hostile_wallpapers(&sandbox, &[("XDG_CURRENT_DESKTOP", "sway")]) {
+        let expected = format!("output * bg {} fill", sway_quoted(image.to_str().unwrap()));
+        assert_eq!(calls.len(), 1, "{:?}", calls);
+        assert_eq!(calls[0][1..].join(" "), expected);
+    }
+}
+
+#[test]
+fn hyprpaper_refuses_paths_with_a_comma() {
+    let sandbox = Sandbox::new("hostile-hyprland");
+    sandbox.program(
+        "hyprctl",
+        &format!(
+            "{{ echo hyprctl; for arg in \"$@\"; do printf '%s\\n' \"$arg\"; done; echo --; }} >> '{}'\necho ok",
+            sandbox.log().display()
+        ),
+    );
+    let hyprland = [("HYPRLAND_INSTANCE_SIGNATURE", "test")];
+
+    for name in HOSTILE_NAMES {
+        let image = sandbox.image(name);
+        let _ = std::fs::remove_file(sandbox.log());
+
+        let output = sandbox.dw(&["set-wallpaper", image.to_str().unwrap()], &hyprland);
+
+        assert!(
+            !sandbox.exists("home/.dwr/pwned"),
+            "{:?} ran a command",
+            name
+        );
+        if name.contains(',') {
+            assert!(!output.status.success(), "{:?} was set", name);
+            assert!(String::from_utf8_lossy(&output.stderr).contains("comma"));
+            assert!(sandbox.calls().is_empty());
+        } else {
+            assert!(output.status.success(), "{:?} failed: {:?}", name, output);
+            let target = format!(",{}", image.display());
+            assert_passed_verbatim(&sandbox.calls(), "hyprctl", &target);
+        }
+    }
+}
+
+#[test]
+fn x11_setter_receives_plain_paths() {
+    let sandbox = Sandbox::new("hostile-x11");
+    sandbox.recorder("feh");
+
+    for (image, calls) in set_hostile_wallpapers(&sandbox, &[("DISPLAY", ":99")]) {
+        assert_passed_verbatim(&calls, "feh", image.to_str().unwrap());
+    }
+}
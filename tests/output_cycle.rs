@@ -0,0 +1,182 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// tests/output_cycle.rs
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::{sway_quoted, Sandbox};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Three file candidates, the first one applied, cycled on DP-1 and HDMI-A-1.
+fn setup(sandbox: &Sandbox, cycle: &str) -> Vec<PathBuf> {
+    let images: Vec<PathBuf> = ["a.png", "b.png", "c.png"]
+        .iter()
+        .map(|name| sandbox.image(name))
+        .collect();
+    let candidates: Vec<String> = images
+        .iter()
+        .map(|image| format!("{:?}", image.display().to_string()))
+        .collect();
+
+    sandbox.write(
+        "home/.dwr/config/config.json",
+        &format!(
+            r#"{{
+                "actual_wallpaper": {{
+                    "index": 0,
+                    "path": {},
+                    "date_set": "2024-07-01T00:00:00-03:00",
+                    "child": false,
+                    "sub_index": 0
+                }},
+                "time_config": {{ "preset": "DAY", "interval": 1 }},
+                "candidates": [{}],
+                "display": {{ "outputs": ["DP-1", "HDMI-A-1"], "cycle": "{}" }}
+            }}"#,
+            candidates[0],
+            candidates.join(", "),
+            cycle
+        ),
+    );
+
+    images
+}
+
+fn swaymsg_call(output: &str, image: &Path) -> Vec<String> {
+    ["swaymsg", "output", output, "bg"]
+        .iter()
+        .map(|arg| arg.to_string())
+        .chain([
+            sway_quoted(&image.display().to_string()),
+            "fill".to_string(),
+        ])
+        .collect()
+}
+
+fn output_index(sandbox: &Sandbox, output: &str) -> u64 {
+    let config: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(sandbox.home().join(".dwr/config/config.json")).unwrap(),
+    )
+    .unwrap();
+
+    config["output_wallpapers"][output]["index"]
+        .as_u64()
+        .unwrap()
+}
+
+#[test]
+fn together_moves_every_output_and_output_flag_moves_one() {
+    let sandbox = Sandbox::new("cycle-together");
+    let images = setup(&sandbox, "together");
+    sandbox.recorder("swaymsg");
+    let sway = [("XDG_CURRENT_DESKTOP", "sway")];
+
+    let output = sandbox.dw(&["next"], &sway);
+    assert!(output.status.success(), "{:?}", output);
+
+    let output = sandbox.dw(&["next", "--output", "HDMI-A-1"], &sway);
+    assert!(output.status.success(), "{:?}", output);
+
+    assert_eq!(
+        sandbox.calls(),
+        vec![
+            swaymsg_call("DP-1", &images[1]),
+            swaymsg_call("HDMI-A-1", &images[1]),
+            swaymsg_call("HDMI-A-1", &images[2]),
+        ]
+    );
+    assert_eq!(output_index(&sandbox, "DP-1"), 1);
+    assert_eq!(output_index(&sandbox, "HDMI-A-1"), 2);
+
+    let output = sandbox.dw(&["previous", "--output", "DP-1"], &sway);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(output_index(&sandbox, "DP-1"), 0);
+}
+
+#[test]
+fn independent_moves_the_output_unchanged_for_longest() {
+    let sandbox = Sandbox::new("cycle-independent");
+    let images = setup(&sandbox, "independent");
+    sandbox.recorder("swaymsg");
+    let sway = [("XDG_CURRENT_DESKTOP", "sway")];
+
+    for _ in 0..3 {
+        let output = sandbox.dw(&["next"], &sway);
+        assert!(output.status.success(), "{:?}", output);
+    }
+
+    assert_eq!(
+        sandbox.calls(),
+        vec![
+            swaymsg_call("DP-1", &images[1]),
+            swaymsg_call("HDMI-A-1", &images[1]),
+            swaymsg_call("DP-1", &images[2]),
+        ]
+    );
+}
+
+#[test]
+fn backends_without_outputs_apply_a_single_image() {
+    let sandbox = Sandbox::new("cycle-single");
+    let images = setup(&sandbox, "together");
+    sandbox.recorder("gsettings");
+
+    let output = sandbox.dw(&["next"], &[("XDG_CURRENT_DESKTOP", "GNOME")]);
+
+    assert!(output.status.success(), "{:?}", output);
+    let calls = sandbox.calls();
+    assert_eq!(calls.len(), 3, "{:?}", calls);
+    assert!(calls[0]
+        .last()
+        .unwrap()
+        .ends_with(images[1].file_name().unwrap().to_str().unwrap()));
+}
+
+#[test]
+fn plain_next_still_moves_every_output_after_an_output_flag() {
+    let sandbox = Sandbox::new("cycle-unlisted");
+    let images = setup(&sandbox, "together");
+    // No `display.outputs`: the outputs only get a cursor from `--output`.
+    let config = fs::read_to_string(sandbox.home().join(".dwr/config/config.json")).unwrap();
+    sandbox.write(
+        "home/.dwr/config/config.json",
+        &config.replace(r#""outputs": ["DP-1", "HDMI-A-1"], "#, ""),
+    );
+    sandbox.recorder("swaymsg");
+    let sway = [("XDG_CURRENT_DESKTOP", "sway")];
+
+    let output = sandbox.dw(&["next", "--output", "DP-1"], &sway);
+    assert!(output.status.success(), "{:?}", output);
+    for _ in 0..2 {
+        let output = sandbox.dw(&["next"], &sway);
+        assert!(output.status.success(), "{:?}", output);
+    }
+
+    assert_eq!(
+        sandbox.calls(),
+        vec![
+            swaymsg_call("DP-1", &images[1]),
+            swaymsg_call("*", &images[1]),
+            swaymsg_call("DP-1", &images[2]),
+            swaymsg_call("*", &images[2]),
+            swaymsg_call("DP-1", &images[0]),
+        ]
+    );
+}
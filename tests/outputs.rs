@@ -0,0 +1,120 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// tests/outputs.rs
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::Sandbox;
+
+/// Runs `dw outputs` with `program` replaying a recorded reply from `fixture`.
+fn outputs(name: &str, program: &str, fixture: &str, vars: &[(&str, &str)]) -> Vec<String> {
+    let sandbox = Sandbox::new(name);
+    sandbox.program(
+        program,
+        &format!(
+            "cat '{}/tests/fixtures/{}'",
+            env!("CARGO_MANIFEST_DIR"),
+            fixture
+        ),
+    );
+
+    let output = sandbox.dw(&["outputs"], vars);
+    assert!(output.status.success(), "{:?}", output);
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.strip_prefix("  "))
+        .map(String::from)
+        .collect()
+}
+
+#[test]
+fn xrandr_skips_disconnected_and_disabled_outputs() {
+    assert_eq!(
+        outputs(
+            "outputs-xrandr",
+            "xrandr",
+            "xrandr-query.txt",
+            &[("DISPLAY", ":0")]
+        ),
+        vec![
+            "eDP-1: 2560x1440 at 0,480 scale 1 rotation 0",
+            "HDMI-1: 1080x1920 at 2560,0 scale 1 rotation 90",
+        ]
+    );
+}
+
+#[test]
+fn wlr_randr_reads_the_current_mode_and_transform() {
+    assert_eq!(
+        outputs(
+            "outputs-wlr-randr",
+            "wlr-randr",
+            "wlr-randr.json",
+            &[("WAYLAND_DISPLAY", "wayland-1")]
+        ),
+        vec![
+            "eDP-1: 2256x1504 at 0,0 scale 1.5 rotation 0",
+            "DP-3: 2160x3840 at 1504,-400 scale 2 rotation 270",
+        ]
+    );
+}
+
+#[test]
+fn swaymsg_skips_inactive_outputs() {
+    assert_eq!(
+        outputs(
+            "outputs-swaymsg",
+            "swaymsg",
+            "swaymsg-get-outputs.json",
+            &[
+                ("SWAYSOCK", "/run/sway.sock"),
+                ("WAYLAND_DISPLAY", "wayland-1")
+            ]
+        ),
+        vec![
+            "eDP-1: 1920x1080 at 0,0 scale 1.25 rotation 0",
+            "DP-2: 1440x2560 at 1536,0 scale 1 rotation 90",
+        ]
+    );
+}
+
+#[test]
+fn hyprctl_rotates_odd_transforms() {
+    assert_eq!(
+        outputs(
+            "outputs-hyprctl",
+            "hyprctl",
+            "hyprctl-monitors.json",
+            &[("HYPRLAND_INSTANCE_SIGNATURE", "abc")]
+        ),
+        vec![
+            "DP-1: 2560x1440 at 0,0 scale 1 rotation 0",
+            "HDMI-A-1: 1080x1920 at 2560,-240 scale 1.25 rotation 270",
+        ]
+    );
+}
+
+#[test]
+fn no_session_is_an_error() {
+    let sandbox = Sandbox::new("outputs-none");
+
+    let output = sandbox.dw(&["outputs"], &[]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No graphical session"));
+}
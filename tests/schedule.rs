@@ -0,0 +1,198 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// tests/schedule.rs
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::{cron_dw, Sandbox};
+use std::fs;
+
+fn config_sandbox(name: &str) -> Sandbox {
+    let sandbox = Sandbox::new(name);
+    sandbox.write(
+        "home/.dwr/config/config.json",
+        r#"{
+            "actual_wallpaper": {
+                "index": 0,
+                "path": "",
+                "date_set": "2024-07-01T00:00:00-03:00",
+                "child": false,
+                "sub_index": 0
+            },
+            "time_config": { "preset": "HOUR", "interval": 1 },
+            "candidates": []
+        }"#,
+    );
+    sandbox
+}
+
+/// Only cron available, with a `crontab` keeping the table in the sandbox.
+fn cron_sandbox(name: &str) -> Sandbox {
+    let sandbox = config_sandbox(name);
+    let table = sandbox.root.join("crontab");
+    sandbox.write("crontab", "");
+    sandbox.program(
+        "crontab",
+        &format!(
+            "case \"$1\" in -l) cat '{0}' ;; -) cat > '{0}' ;; esac",
+            table.display()
+        ),
+    );
+    sandbox
+}
+
+/// A systemd user manager with a fake `systemctl`.
+fn systemd_sandbox(name: &str) -> Sandbox {
+    let sandbox = config_sandbox(name);
+    sandbox.recorder("systemctl");
+    fs::create_dir_all(sandbox.root.join("run/systemd")).unwrap();
+    sandbox
+}
+
+fn dw_ok(sandbox: &Sandbox, args: &[&str]) {
+    let output = sandbox.dw(args, &[]);
+    assert!(output.status.success(), "{:?}", output);
+}
+
+fn cron_entries(sandbox: &Sandbox) -> Vec<String> {
+    fs::read_to_string(sandbox.root.join("crontab"))
+        .unwrap()
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .map(String::from)
+        .collect()
+}
+
+fn unit(sandbox: &Sandbox, name: &str) -> String {
+    fs::read_to_string(sandbox.home().join(".config/systemd/user").join(name)).unwrap()
+}
+
+#[test]
+fn day_preset_changes_at_the_chosen_time() {
+    let sandbox = cron_sandbox("schedule-day");
+
+    dw_ok(&sandbox, &["preset", "day", "1", "--at", "07:30"]);
+    dw_ok(&sandbox, &["on"]);
+
+    assert_eq!(
+        cron_entries(&sandbox),
+        [
+            format!("30 7 * * * {} tick", cron_dw()),
+            format!("*/15 * * * * {} tick > /dev/null", cron_dw())
+        ]
+    );
+}
+
+#[test]
+fn every_few_days_runs_daily_and_leaves_the_count_to_tick() {
+    let sandbox = cron_sandbox("schedule-days-cron");
+    dw_ok(&sandbox, &["preset", "day", "3", "--at", "06:00"]);
+    dw_ok(&sandbox, &["on"]);
+    assert_eq!(
+        cron_entries(&sandbox),
+        [
+            format!("0 6 * * * {} tick", cron_dw()),
+            format!("*/15 * * * * {} tick > /dev/null", cron_dw())
+        ]
+    );
+
+    let sandbox = systemd_sandbox("schedule-days-systemd");
+    dw_ok(&sandbox, &["preset", "day", "3", "--at", "06:00"]);
+    dw_ok(&sandbox, &["on"]);
+    let timer = unit(&sandbox, "dwr-next.timer");
+    assert!(timer.contains("\nOnCalendar=*-*-* 06:00:00\n"), "{}", timer);
+    let service = unit(&sandbox, "dwr-next.service");
+    assert!(!service.contains("ExecCondition"), "{}", service);
+}
+
+#[test]
+fn times_and_weekdays_are_translated_for_each_scheduler() {
+    let args = [
+        "schedule",
+        "--times",
+        "19:00,7:00",
+        "--weekdays",
+        "fri,Monday",
+    ];
+
+    let sandbox = cron_sandbox("schedule-times-cron");
+    dw_ok(&sandbox, &args);
+    dw_ok(&sandbox, &["on"]);
+    assert_eq!(
+        cron_entries(&sandbox),
+        [
+            format!("0 7 * * mon,fri {} tick", cron_dw()),
+            format!("0 19 * * mon,fri {} tick", cron_dw()),
+            format!("*/15 * * * * {} tick > /dev/null", cron_dw())
+        ]
+    );
+
+    let sandbox = systemd_sandbox("schedule-times-systemd");
+    dw_ok(&sandbox, &args);
+    dw_ok(&sandbox, &["on"]);
+    let timer = unit(&sandbox, "dwr-next.timer");
+    assert!(
+        timer.contains("\nOnCalendar=Mon,Fri *-*-* 07:00:00\nOnCalendar=Mon,Fri *-*-* 19:00:00\n"),
+        "{}",
+        timer
+    );
+}
+
+#[test]
+fn raw_expressions_go_to_their_scheduler_only() {
+    let sandbox = cron_sandbox("schedule-raw-cron");
+    dw_ok(&sandbox, &["schedule", "--cron", "15 8-18/2 * * 1-5"]);
+    dw_ok(&sandbox, &["on"]);
+    assert_eq!(
+        cron_entries(&sandbox),
+        [format!("15 8-18/2 * * 1-5 {} next", cron_dw())]
+    );
+
+    let sandbox = systemd_sandbox("schedule-raw-systemd");
+    dw_ok(&sandbox, &["schedule", "--cron", "@hourly"]);
+    let output = sandbox.dw(&["on"], &[]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("on_calendar"));
+}
+
+#[test]
+fn invalid_schedules_are_refused_before_being_saved() {
+    let sandbox = cron_sandbox("schedule-invalid");
+    let config = sandbox.home().join(".dwr/config/config.json");
+    let before = fs::read_to_string(&config).unwrap();
+
+    for args in [
+        &["schedule", "--times", "24:00"][..],
+        &["schedule", "--times", "7:5"],
+        &["schedule", "--weekdays", "someday"],
+        &["schedule", "--cron", "* * * *"],
+        &["schedule", "--cron", "0 0 * * * rm -rf"],
+        &["preset", "hour", "0"],
+        &["preset", "minute", "5", "--at", "07:00"],
+    ] {
+        let output = sandbox.dw(args, &[]);
+        assert_eq!(output.status.code(), Some(1), "{:?}", args);
+        assert!(
+            String::from_utf8_lossy(&output.stderr).contains("(exit code 49)"),
+            "{:?}: {:?}",
+            args,
+            output
+        );
+    }
+
+    assert_eq!(fs::read_to_string(&config).unwrap(), before);
+}
@@ -0,0 +1,212 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// tests/span.rs
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::{sway_quoted, Sandbox};
+use std::{fs, path::PathBuf};
+
+const SWAY: [(&str, &str); 1] = [("SWAYSOCK", "/run/sway.sock")];
+
+/// Two 1920x1080 outputs side by side, spanning with a 100 pixel bezel, and an
+/// ImageMagick reporting every image as `size`.
+fn setup(sandbox: &Sandbox, size: &str) {
+    sandbox.write(
+        "home/.dwr/config/config.json",
+        r#"{
+            "actual_wallpaper": {
+                "index": 0,
+                "path": "",
+                "date_set": "2024-07-01T00:00:00-03:00",
+                "child": false,
+                "sub_index": 0
+            },
+            "time_config": { "preset": "DAY", "interval": 1 },
+            "candidates": [],
+            "display": { "span": true, "bezel": 100 }
+        }"#,
+    );
+    sandbox.write(
+        "outputs.json",
+        r#"[
+            { "name": "DP-1", "active": true, "rect": { "x": 0, "y": 0 }, "scale": 1.0,
+              "transform": "normal", "current_mode": { "width": 1920, "height": 1080 } },
+            { "name": "DP-2", "active": true, "rect": { "x": 1920, "y": 0 }, "scale": 1.0,
+              "transform": "normal", "current_mode": { "width": 1920, "height": 1080 } }
+        ]"#,
+    );
+    sandbox.write("size", &format!("{}\n", size));
+
+    let record = format!(
+        r#"{{ echo "$(basename "$0")"; for arg in "$@"; do echo "$arg"; done; echo --; }} >> '{}'"#,
+        sandbox.log().display()
+    );
+    sandbox.program(
+        "swaymsg",
+        &format!(
+            "case \"$2\" in\n  get_outputs) cat '{}' ;;\n  *) {} ;;\nesac",
+            sandbox.root.join("outputs.json").display(),
+            record
+        ),
+    );
+    sandbox.program(
+        "magick",
+        &format!(
+            "case \"$1\" in\n  identify) cat '{}' ;;\n  *) {}; for last; do :; done; : > \"$last\" ;;\nesac",
+            sandbox.root.join("size").display(),
+            record
+        ),
+    );
+}
+
+#[test]
+fn panoramic_images_are_cut_per_output_and_cached() {
+    let sandbox = Sandbox::new("span-panoramic");
+    setup(&sandbox, "7880 2160");
+    let image = sandbox.image("panorama.png");
+    let image_as_str = image.to_str().unwrap();
+
+    let output = sandbox.dw(&["set-wallpaper", image_as_str], &SWAY);
+    assert!(output.status.success(), "{:?}", output);
+
+    let calls = sandbox.calls();
+    assert_eq!(calls.len(), 4, "{:?}", calls);
+    // The image is scaled by half to cover the 3940 pixels wide layout, and
+    // DP-2 starts 100 pixels after the end of DP-1.
+    assert_eq!(
+        calls[0][..7],
+        [
+            "magick",
+            image_as_str,
+            "-crop",
+            "3840x2160+0+0",
+            "+repage",
+            "-resize",
+            "1920x1080!"
+        ]
+    );
+    assert_eq!(calls[1][3], "3840x2160+4040+0");
+
+    let slice = |call: &Vec<String>| call.last().unwrap().clone();
+    assert!(slice(&calls[0]).ends_with("-DP-1.png"));
+    assert!(slice(&calls[0]).starts_with(
+        &sandbox
+            .home()
+            .join(".dwr/state/span/")
+            .display()
+            .to_string()
+    ));
+    assert_eq!(
+        calls[2],
+        [
+            "swaymsg",
+            "output",
+            "DP-1",
+            "bg",
+            &sway_quoted(&slice(&calls[0])),
+            "fill"
+        ]
+    );
+    assert_eq!(
+        calls[3],
+        [
+            "swaymsg",
+            "output",
+            "DP-2",
+            "bg",
+            &sway_quoted(&slice(&calls[1])),
+            "fill"
+        ]
+    );
+
+    // The second time the cached slices are used as they are.
+    let output = sandbox.dw(&["set-wallpaper", image_as_str], &SWAY);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(sandbox.calls()[4..], calls[2..]);
+}
+
+#[test]
+fn slices_of_the_previous_image_are_dropped() {
+    let sandbox = Sandbox::new("span-prune");
+    setup(&sandbox, "7880 2160");
+    let first = sandbox.image("first.png");
+    let second = sandbox.image("second.png");
+
+    let output = sandbox.dw(&["set-wallpaper", first.to_str().unwrap()], &SWAY);
+    assert!(output.status.success(), "{:?}", output);
+    let old = PathBuf::from(sandbox.calls()[0].last().unwrap());
+    assert!(old.exists());
+
+    let output = sandbox.dw(&["set-wallpaper", second.to_str().unwrap()], &SWAY);
+    assert!(output.status.success(), "{:?}", output);
+
+    assert!(!old.exists());
+    let cached = fs::read_dir(sandbox.home().join(".dwr/state/span"))
+        .unwrap()
+        .count();
+    assert_eq!(cached, 2);
+}
+
+#[test]
+fn regular_images_are_not_spanned() {
+    let sandbox = Sandbox::new("span-regular");
+    setup(&sandbox, "1920 1080");
+    let image = sandbox.image("regular.png");
+
+    let output = sandbox.dw(&["set-wallpaper", image.to_str().unwrap()], &SWAY);
+
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        sandbox.calls(),
+        [[
+            "swaymsg",
+            "output",
+            "*",
+            "bg",
+            &sway_quoted(image.to_str().unwrap()),
+            "fill"
+        ]]
+    );
+}
+
+#[test]
+fn plasma_gets_the_whole_image_since_it_numbers_its_screens() {
+    let sandbox = Sandbox::new("span-plasma");
+    setup(&sandbox, "7880 2160");
+    sandbox.program(
+        "xrandr",
+        &format!(
+            "cat '{}/tests/fixtures/xrandr-query.txt'",
+            env!("CARGO_MANIFEST_DIR")
+        ),
+    );
+    sandbox.recorder("qdbus");
+    let image = sandbox.image("panorama.png");
+
+    let output = sandbox.dw(
+        &["set-wallpaper", image.to_str().unwrap()],
+        &[("XDG_CURRENT_DESKTOP", "KDE"), ("DISPLAY", ":99")],
+    );
+
+    assert!(output.status.success(), "{:?}", output);
+    let calls = sandbox.calls();
+    assert_eq!(calls.len(), 1, "{:?}", calls);
+    assert_eq!(calls[0][0], "qdbus");
+    let uri = format!("file://{}", image.display());
+    assert!(calls[0].iter().any(|arg| arg.contains(&uri)), "{:?}", calls);
+}
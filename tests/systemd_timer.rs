@@ -0,0 +1,192 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// tests/systemd_timer.rs
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::{cron_dw, Sandbox};
+use std::fs;
+
+/// A sandbox with a running user manager: a fake `systemctl` recorder and the
+/// runtime directory systemd would create.
+fn user_manager(name: &str) -> Sandbox {
+    let sandbox = Sandbox::new(name);
+    sandbox.recorder("systemctl");
+    fs::create_dir_all(sandbox.root.join("run/systemd")).unwrap();
+    sandbox
+}
+
+/// A `crontab` keeping the table in the sandbox.
+fn fake_crontab(sandbox: &Sandbox, table: &str) {
+    sandbox.write("crontab", table);
+    sandbox.program(
+        "crontab",
+        &format!(
+            "case \"$1\" in -l) cat '{0}' ;; -) cat > '{0}' ;; esac",
+            sandbox.root.join("crontab").display()
+        ),
+    );
+}
+
+fn write_config(sandbox: &Sandbox, preset: &str, interval: u8, extra: &str) {
+    sandbox.write(
+        "home/.dwr/config/config.json",
+        &format!(
+            r#"{{
+                "actual_wallpaper": {{
+                    "index": 0,
+                    "path": "",
+                    "date_set": "2024-07-01T00:00:00-03:00",
+                    "child": false,
+                    "sub_index": 0
+                }},
+                "time_config": {{ "preset": "{}", "interval": {} }},
+                "candidates": []{}
+            }}"#,
+            preset, interval, extra
+        ),
+    );
+}
+
+fn unit(sandbox: &Sandbox, name: &str) -> String {
+    fs::read_to_string(sandbox.home().join(".config/systemd/user").join(name)).unwrap()
+}
+
+fn systemctl(args: &[&str]) -> Vec<String> {
+    ["systemctl", "--user"]
+        .iter()
+        .chain(args)
+        .map(|arg| arg.to_string())
+        .collect()
+}
+
+#[test]
+fn on_writes_and_starts_the_timer_and_drops_the_cron_block() {
+    let sandbox = user_manager("systemd-on");
+    fake_crontab(
+        &sandbox,
+        &format!(
+            "@daily backup\n# BEGIN dwr\n0 */1 * * * {} tick\n# END dwr\n",
+            cron_dw()
+        ),
+    );
+    write_config(&sandbox, "HOUR", 2, "");
+
+    let output = sandbox.dw(&["on"], &[]);
+
+    assert!(output.status.success(), "{:?}", output);
+    let timer = unit(&sandbox, "dwr-next.timer");
+    assert!(
+        timer.contains("\nOnCalendar=0/2:00\nOnCalendar=*:0/15\nPersistent=true\n"),
+        "{}",
+        timer
+    );
+    assert!(timer.contains("WantedBy=timers.target"), "{}", timer);
+    let service = unit(&sandbox, "dwr-next.service");
+    let exec_start = format!(
+        "ExecStart=\"{}\" tick\n",
+        env!("CARGO_BIN_EXE_daily-wallpapers-rust")
+    );
+    assert!(service.contains(&exec_start), "{}", service);
+    assert!(service.contains("Type=oneshot"), "{}", service);
+    assert_eq!(
+        sandbox.calls(),
+        vec![
+            systemctl(&["daemon-reload"]),
+            systemctl(&["enable", "dwr-next.timer"]),
+            systemctl(&["restart", "dwr-next.timer"]),
+        ]
+    );
+    assert_eq!(
+        fs::read_to_string(sandbox.root.join("crontab")).unwrap(),
+        "@daily backup\n"
+    );
+}
+
+#[test]
+fn uneven_intervals_count_from_the_last_run_or_are_caught_up() {
+    let sandbox = user_manager("systemd-monotonic");
+    write_config(&sandbox, "MINUTE", 7, "");
+
+    let output = sandbox.dw(&["on"], &[]);
+
+    assert!(output.status.success(), "{:?}", output);
+    let timer = unit(&sandbox, "dwr-next.timer");
+    assert!(
+        timer.contains("\nOnActiveSec=7min\nOnUnitActiveSec=7min\nPersistent=true\n"),
+        "{}",
+        timer
+    );
+
+    // Longer ones are left to `tick` every 15 minutes, which, unlike a count
+    // stopped during a suspend, catches up soon after waking.
+    let sandbox = user_manager("systemd-catch-up");
+    write_config(&sandbox, "HOUR", 5, "");
+
+    let output = sandbox.dw(&["on"], &[]);
+
+    assert!(output.status.success(), "{:?}", output);
+    let timer = unit(&sandbox, "dwr-next.timer");
+    assert!(
+        timer.contains("[Timer]\nOnCalendar=*:0/15\nPersistent=true\n"),
+        "{}",
+        timer
+    );
+}
+
+#[test]
+fn off_stops_and_removes_the_timer() {
+    let sandbox = user_manager("systemd-off");
+    write_config(&sandbox, "MINUTE", 30, "");
+
+    let output = sandbox.dw(&["on"], &[]);
+    assert!(output.status.success(), "{:?}", output);
+    fs::remove_file(sandbox.log()).unwrap();
+
+    let output = sandbox.dw(&["off"], &[]);
+
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        sandbox.calls(),
+        vec![
+            systemctl(&["disable", "--now", "dwr-next.timer"]),
+            systemctl(&["daemon-reload"]),
+        ]
+    );
+    assert!(!sandbox.exists("home/.config/systemd/user/dwr-next.timer"));
+    assert!(!sandbox.exists("home/.config/systemd/user/dwr-next.service"));
+}
+
+#[test]
+fn scheduler_from_the_config_wins_over_detection() {
+    let sandbox = user_manager("systemd-forced-cron");
+    fake_crontab(&sandbox, "");
+    write_config(&sandbox, "HOUR", 1, r#", "scheduler": "cron""#);
+
+    let output = sandbox.dw(&["on"], &[]);
+
+    assert!(output.status.success(), "{:?}", output);
+    assert!(sandbox.calls().is_empty());
+    assert!(!sandbox.exists("home/.config/systemd/user/dwr-next.timer"));
+    assert_eq!(
+        fs::read_to_string(sandbox.root.join("crontab")).unwrap(),
+        format!(
+            "# BEGIN dwr\n0 */1 * * * {0} tick\n*/15 * * * * {0} tick > /dev/null\n# END dwr\n",
+            cron_dw()
+        )
+    );
+}
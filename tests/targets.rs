@@ -0,0 +1,171 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// tests/targets.rs
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::{sway_quoted, Sandbox};
+use std::{fs, path::PathBuf};
+
+fn write_config(sandbox: &Sandbox, candidates: &[PathBuf], targets: &str) {
+    let quoted: Vec<String> = candidates
+        .iter()
+        .map(|candidate| format!("{:?}", candidate.display().to_string()))
+        .collect();
+
+    sandbox.write(
+        "home/.dwr/config/config.json",
+        &format!(
+            r#"{{
+                "actual_wallpaper": {{
+                    "index": 0,
+                    "path": {},
+                    "date_set": "2024-07-01T00:00:00-03:00",
+                    "child": false,
+                    "sub_index": 0
+                }},
+                "time_config": {{ "preset": "DAY", "interval": 1 }},
+                "candidates": [{}],
+                "targets": {}
+            }}"#,
+            quoted[0],
+            quoted.join(", "),
+            targets
+        ),
+    );
+}
+
+#[test]
+fn gnome_lock_screen_follows_the_desktop() {
+    let sandbox = Sandbox::new("targets-gnome");
+    let image = sandbox.image("desk.png");
+    write_config(
+        &sandbox,
+        std::slice::from_ref(&image),
+        r#"[{ "target": "lockscreen" }]"#,
+    );
+    sandbox.recorder("gsettings");
+
+    let output = sandbox.dw(
+        &["set-wallpaper", image.to_str().unwrap()],
+        &[("XDG_CURRENT_DESKTOP", "GNOME")],
+    );
+
+    assert!(output.status.success(), "{:?}", output);
+    let calls = sandbox.calls();
+    assert_eq!(calls.len(), 5, "{:?}", calls);
+    assert_eq!(
+        calls[3],
+        [
+            "gsettings",
+            "set",
+            "org.gnome.desktop.screensaver",
+            "picture-uri",
+            &format!("file://{}", image.display())
+        ]
+    );
+}
+
+#[test]
+fn kde_lock_screen_is_written_to_kscreenlockerrc() {
+    let sandbox = Sandbox::new("targets-kde");
+    let image = sandbox.image("desk.png");
+    write_config(
+        &sandbox,
+        std::slice::from_ref(&image),
+        r#"[{ "target": "lockscreen" }]"#,
+    );
+    sandbox.recorder("qdbus6");
+    sandbox.recorder("kwriteconfig6");
+
+    let output = sandbox.dw(
+        &["set-wallpaper", image.to_str().unwrap()],
+        &[("XDG_CURRENT_DESKTOP", "KDE")],
+    );
+
+    assert!(output.status.success(), "{:?}", output);
+    let calls = sandbox.calls();
+    assert_eq!(calls[0][0], "qdbus6");
+    assert_eq!(
+        calls[2],
+        [
+            "kwriteconfig6",
+            "--file",
+            "kscreenlockerrc",
+            "--group",
+            "Greeter",
+            "--group",
+            "Wallpaper",
+            "--group",
+            "org.kde.image",
+            "--group",
+            "General",
+            "--key",
+            "Image",
+            &format!("file://{}", image.display())
+        ]
+    );
+}
+
+#[test]
+fn swaylock_runs_its_own_cycle_and_greeter_follows_the_desktop() {
+    let sandbox = Sandbox::new("targets-sway");
+    let desktop: Vec<PathBuf> = ["d1.png", "d2.png"]
+        .iter()
+        .map(|name| sandbox.image(name))
+        .collect();
+    let lock: Vec<PathBuf> = ["l1.png", "l2.png"]
+        .iter()
+        .map(|name| sandbox.image(name))
+        .collect();
+    let greeter = sandbox.root.join("greeter-background");
+    write_config(
+        &sandbox,
+        &desktop,
+        &format!(
+            r#"[
+                {{ "target": "lockscreen", "candidates": [{:?}, {:?}] }},
+                {{ "target": "greeter", "file": {:?} }}
+            ]"#,
+            lock[0].display().to_string(),
+            lock[1].display().to_string(),
+            greeter.display().to_string()
+        ),
+    );
+    sandbox.recorder("swaymsg");
+    let sway = [("SWAYSOCK", "/run/sway.sock")];
+    let swaylock_config = sandbox.home().join(".dwr/state/swaylock/config");
+
+    let output = sandbox.dw(&["next"], &sway);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        fs::read_to_string(&swaylock_config).unwrap(),
+        format!("image={}\nscaling=fill\n", lock[0].display())
+    );
+    assert!(sandbox.exists("greeter-background"));
+
+    let output = sandbox.dw(&["next"], &sway);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        fs::read_to_string(&swaylock_config).unwrap(),
+        format!("image={}\nscaling=fill\n", lock[1].display())
+    );
+    assert_eq!(
+        sandbox.calls()[1][4],
+        sway_quoted(&desktop[0].display().to_string())
+    );
+}
@@ -0,0 +1,178 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// tests/tick.rs
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use chrono::{Duration, Local};
+use common::Sandbox;
+use std::{fs, path::PathBuf};
+
+/// Four images cycled every ten minutes, the first one set `minutes_ago`, with
+/// a recording `setter`.
+fn tick_sandbox(name: &str, minutes_ago: i64, time_config: &str) -> (Sandbox, Vec<PathBuf>) {
+    let sandbox = Sandbox::new(name);
+    sandbox.recorder("setter");
+    let images: Vec<PathBuf> = ["a.png", "b.png", "c.png", "d.png"]
+        .iter()
+        .map(|name| sandbox.image(name))
+        .collect();
+    let candidates: Vec<String> = images
+        .iter()
+        .map(|image| format!("\"{}\"", image.display()))
+        .collect();
+    sandbox.write(
+        "home/.dwr/config/config.json",
+        &format!(
+            r#"{{
+                "actual_wallpaper": {{
+                    "index": 0,
+                    "path": "{}",
+                    "date_set": "{}",
+                    "child": false,
+                    "sub_index": 0
+                }},
+                "time_config": {},
+                "candidates": [{}],
+                "backend": {{ "command": ["setter", "{{path}}"] }}
+            }}"#,
+            images[0].display(),
+            (Local::now() - Duration::minutes(minutes_ago)).to_rfc3339(),
+            time_config,
+            candidates.join(", ")
+        ),
+    );
+    (sandbox, images)
+}
+
+const EVERY_TEN_MINUTES: &str = r#"{ "preset": "MINUTE", "interval": 10 }"#;
+
+fn setter_calls(sandbox: &Sandbox) -> Vec<String> {
+    sandbox
+        .calls()
+        .into_iter()
+        .map(|call| call[1].clone())
+        .collect()
+}
+
+#[test]
+fn changes_only_once_a_change_is_due() {
+    let (sandbox, _) = tick_sandbox("tick-due", 5, EVERY_TEN_MINUTES);
+
+    let output = sandbox.dw(&["tick"], &[]);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("No change due until"));
+    assert!(sandbox.calls().is_empty());
+
+    let (sandbox, images) = tick_sandbox("tick-due-later", 12, EVERY_TEN_MINUTES);
+    for _ in 0..3 {
+        let output = sandbox.dw(&["tick"], &[]);
+        assert!(output.status.success(), "{:?}", output);
+    }
+
+    // Ticking again right after the change doesn't move on.
+    assert_eq!(setter_calls(&sandbox), [images[1].display().to_string()]);
+}
+
+#[test]
+fn missed_changes_are_made_once_or_all_at_once() {
+    // Changes were due 25, 15 and 5 minutes ago.
+    let (sandbox, images) = tick_sandbox("tick-single", 35, EVERY_TEN_MINUTES);
+    let output = sandbox.dw(&["tick"], &[]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(setter_calls(&sandbox), [images[1].display().to_string()]);
+
+    let (sandbox, images) = tick_sandbox("tick-catch-up", 35, EVERY_TEN_MINUTES);
+    let output = sandbox.dw(&["tick", "--catch-up"], &[]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(setter_calls(&sandbox), [images[3].display().to_string()]);
+
+    let (sandbox, images) = tick_sandbox(
+        "tick-catch-up-config",
+        35,
+        r#"{ "preset": "MINUTE", "interval": 10, "catch_up": true }"#,
+    );
+    let output = sandbox.dw(&["tick"], &[]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(setter_calls(&sandbox), [images[3].display().to_string()]);
+}
+
+#[test]
+fn a_daily_change_missed_overnight_is_made() {
+    let (sandbox, images) = tick_sandbox(
+        "tick-overnight",
+        36 * 60,
+        r#"{ "preset": "DAY", "interval": 1, "at": "07:00" }"#,
+    );
+
+    let output = sandbox.dw(&["tick"], &[]);
+
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(setter_calls(&sandbox), [images[1].display().to_string()]);
+    let config = fs::read_to_string(sandbox.root.join("home/.dwr/config/config.json")).unwrap();
+    assert!(config.contains("\"index\": 1"), "{}", config);
+}
+
+#[test]
+fn every_few_days_counts_from_the_last_change() {
+    // The third `at` after a change made three days and four hours ago was
+    // yesterday, after one made a day and four hours ago it's tomorrow,
+    // whatever day since 1970 it is.
+    let at = (Local::now() - Duration::hours(2)).format("%H:%M");
+    let time_config = format!(r#"{{ "preset": "DAY", "interval": 3, "at": "{}" }}"#, at);
+
+    let (sandbox, images) = tick_sandbox("tick-days-due", (3 * 24 + 4) * 60, &time_config);
+    let output = sandbox.dw(&["tick"], &[]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(setter_calls(&sandbox), [images[1].display().to_string()]);
+
+    let (sandbox, _) = tick_sandbox("tick-days-early", (24 + 4) * 60, &time_config);
+    let output = sandbox.dw(&["tick"], &[]);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(sandbox.calls().is_empty());
+}
+
+#[test]
+fn a_failed_change_is_made_again_on_the_next_tick() {
+    let (sandbox, images) = tick_sandbox("tick-failed", 12, EVERY_TEN_MINUTES);
+    let before = fs::read_to_string(sandbox.root.join("home/.dwr/config/config.json")).unwrap();
+    sandbox.program("setter", "exit 1");
+
+    let output = sandbox.dw(&["tick"], &[]);
+    assert!(!output.status.success());
+    let config = fs::read_to_string(sandbox.root.join("home/.dwr/config/config.json")).unwrap();
+    assert_eq!(config, before);
+
+    sandbox.recorder("setter");
+    let output = sandbox.dw(&["tick"], &[]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(setter_calls(&sandbox), [images[1].display().to_string()]);
+}
+
+#[test]
+fn raw_expressions_are_left_to_their_scheduler() {
+    let (sandbox, _) = tick_sandbox(
+        "tick-raw",
+        60,
+        r#"{ "preset": "HOUR", "interval": 1, "cron": "0 * * * *" }"#,
+    );
+
+    let output = sandbox.dw(&["tick"], &[]);
+
+    assert!(!output.status.success());
+    assert!(sandbox.calls().is_empty());
+}
@@ -0,0 +1,132 @@
+// Copyright 2024 Gustavo Mantovani
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// tests/workspaces.rs
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::{sway_quoted, Sandbox};
+use std::path::{Path, PathBuf};
+
+const SWAY: [(&str, &str); 1] = [("SWAYSOCK", "/run/sway.sock")];
+
+/// Workspace "2" (focused, on DP-1) has its own three images, everything else
+/// shows `default.png`.
+fn setup(sandbox: &Sandbox) -> (PathBuf, Vec<PathBuf>) {
+    let default = sandbox.image("default.png");
+    let images: Vec<PathBuf> = ["w1.png", "w2.png", "w3.png"]
+        .iter()
+        .map(|name| sandbox.image(name))
+        .collect();
+    let quoted: Vec<String> = images
+        .iter()
+        .map(|image| format!("{:?}", image.display().to_string()))
+        .collect();
+
+    sandbox.write(
+        "home/.dwr/config/config.json",
+        &format!(
+            r#"{{
+                "actual_wallpaper": {{
+                    "index": 0,
+                    "path": "{}",
+                    "date_set": "2024-07-01T00:00:00-03:00",
+                    "child": false,
+                    "sub_index": 0
+                }},
+                "time_config": {{ "preset": "DAY", "interval": 1 }},
+                "candidates": ["{}"],
+                "workspaces": {{ "2": [{}], "3": [{}] }}
+            }}"#,
+            default.display(),
+            default.display(),
+            quoted.join(", "),
+            quoted.join(", ")
+        ),
+    );
+
+    // Answers the IPC queries, replays `events` to subscribers and records the rest.
+    sandbox.write(
+        "events.json",
+        r#"{ "change": "focus", "current": { "name": "2", "output": "DP-1" } }
+{ "change": "init", "current": { "name": "4", "output": "DP-1" } }
+{ "change": "focus", "current": { "name": "1", "output": "HDMI-A-1" } }
+{ "change": "focus", "current": { "name": "2", "output": "DP-1" } }
+"#,
+    );
+    sandbox.program(
+        "swaymsg",
+        &format!(
+            r#"case "$2" in
+  get_workspaces) echo '[{{"name":"1","output":"HDMI-A-1","focused":false}},{{"name":"2","output":"DP-1","focused":true}}]' ;;
+  subscribe) cat '{}' ;;
+  *) {{ echo swaymsg; for arg in "$@"; do echo "$arg"; done; echo --; }} >> '{}' ;;
+esac"#,
+            sandbox.root.join("events.json").display(),
+            sandbox.log().display()
+        ),
+    );
+
+    (default, images)
+}
+
+fn bg(output: &str, image: &Path) -> Vec<String> {
+    vec![
+        "swaymsg".to_string(),
+        "output".to_string(),
+        output.to_string(),
+        "bg".to_string(),
+        sway_quoted(&image.display().to_string()),
+        "fill".to_string(),
+    ]
+}
+
+#[test]
+fn next_cycles_the_focused_workspace_set() {
+    let sandbox = Sandbox::new("workspaces-next");
+    let (_, images) = setup(&sandbox);
+
+    let output = sandbox.dw(&["next"], &SWAY);
+    assert!(output.status.success(), "{:?}", output);
+
+    // Workspace 3 isn't focused, its cursor moves without touching the screen.
+    let output = sandbox.dw(&["next", "--workspace", "3"], &SWAY);
+    assert!(output.status.success(), "{:?}", output);
+
+    let output = sandbox.dw(&["next"], &SWAY);
+    assert!(output.status.success(), "{:?}", output);
+
+    assert_eq!(
+        sandbox.calls(),
+        vec![bg("DP-1", &images[1]), bg("DP-1", &images[2])]
+    );
+}
+
+#[test]
+fn watcher_follows_the_focused_workspace() {
+    let sandbox = Sandbox::new("workspaces-watch");
+    let (default, images) = setup(&sandbox);
+
+    let output = sandbox.dw(&["watch-workspaces"], &SWAY);
+
+    // The fake event stream ends after the recorded events.
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("event stream ended"));
+    assert_eq!(
+        sandbox.calls(),
+        vec![bg("DP-1", &images[0]), bg("HDMI-A-1", &default)]
+    );
+}